@@ -11,21 +11,34 @@ const FLAGS: [&str; 7] = [
 const DEFINES: [(&str, &str); 5] = [
     // ("BASISU_FORCE_DEVEL_MESSAGES", "1"),
     // ("BASISD_SUPPORT_KTX2", "1"),
-    // ("BASISD_SUPPORT_KTX2_ZSTD", "1"),
-    // ("BASISD_SUPPORT_UASTC", "1"),
     ("BASISD_SUPPORT_DXT1", "0"), //(BC1)
-    // ("BASISD_SUPPORT_DXT5A", "1"), //(BC3 / 4 / 5)
-    // ("BASISD_SUPPORT_BC7", "1"),
-    // ("BASISD_SUPPORT_BC7_MODE5", "1"),
     ("BASISD_SUPPORT_PVRTC1", "0"),
-    // ("BASISD_SUPPORT_ETC2_EAC_A8", "1"),
-    // ("BASISD_SUPPORT_ASTC", "1"),
     ("BASISD_SUPPORT_ATC", "0"),
     // ("BASISD_SUPPORT_ASTC_HIGHER_OPAQUE_QUALITY", "1"),
-    // ("BASISD_SUPPORT_ETC2_EAC_RG11", "1"),
     ("BASISD_SUPPORT_FXT1", "0"),
     ("BASISD_SUPPORT_PVRTC2", "0"),
-    // ("BASISD_SUPPORT_UASTC_HDR", "1"),
+];
+// The optional source and target formats, each define is 1 if the format is enabled and 0 otherwise.
+// Native builds enable the formats with the cargo features of the same name, wasm builds with `build-wasm-cli --formats`.
+// ETC1S and the uncompressed targets are always compiled, Basis Universal can't disable them.
+// The BC6H and ASTC HDR targets are part of `BASISD_SUPPORT_UASTC_HDR`.
+const FORMAT_DEFINES: [(&str, &[&str]); 6] = [
+    ("uastc-ldr", &["BASISD_SUPPORT_UASTC"]),
+    ("uastc-hdr", &["BASISD_SUPPORT_UASTC_HDR"]),
+    (
+        "target-bc",
+        &[
+            "BASISD_SUPPORT_DXT5A", //(BC3 / 4 / 5)
+            "BASISD_SUPPORT_BC7",
+            "BASISD_SUPPORT_BC7_MODE5",
+        ],
+    ),
+    ("target-astc", &["BASISD_SUPPORT_ASTC"]),
+    (
+        "target-etc2",
+        &["BASISD_SUPPORT_ETC2_EAC_A8", "BASISD_SUPPORT_ETC2_EAC_RG11"],
+    ),
+    ("zstd", &["BASISD_SUPPORT_KTX2_ZSTD"]),
 ];
 const SRCS: [&str; 3] = [
     "vendor/basis_universal/transcoder/basisu_transcoder.cpp",
@@ -52,13 +65,38 @@ fn bindgen() {
         .use_core()
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .allowlist_type("Transcoder")
+        .allowlist_type("TranscoderState")
         .allowlist_type("TextureTranscodedFormat")
-        .allowlist_type("TextureCompressionMethod")
+        .allowlist_type("BasisTexFormat")
+        .allowlist_type("TranscodeErrorCode")
+        .allowlist_type("DecodeFlags")
+        .allowlist_type("CompiledTranscodeFormats")
         .allowlist_function("c_basisu_transcoder_init")
+        .allowlist_function("c_basisu_get_compiled_formats")
         .allowlist_function("c_ktx2_transcoder_new")
         .allowlist_function("c_ktx2_transcoder_delete")
         .allowlist_function("c_ktx2_transcoder_transcode_image")
-        .allowlist_function("c_ktx2_transcoder_get_r_dst_buf")
+        .allowlist_function("c_basis_transcoder_transcode_image")
+        .allowlist_function("c_basis_transcoder_init")
+        .allowlist_function("c_basis_transcoder_prepare")
+        .allowlist_function("c_basis_transcoder_transcode")
+        .allowlist_function("c_ktx2_transcoder_set_external_dst_buf")
+        .allowlist_function("c_ktx2_transcoder_set_decode_flags")
+        .allowlist_function("c_ktx2_transcoder_set_dst_buf")
+        .allowlist_function("c_ktx2_transcoder_init")
+        .allowlist_function("c_ktx2_transcoder_prepare")
+        .allowlist_function("c_ktx2_transcoder_get_slices")
+        .allowlist_function("c_ktx2_transcoder_get_slice_offset")
+        .allowlist_function("c_ktx2_transcoder_state_new")
+        .allowlist_function("c_ktx2_transcoder_state_delete")
+        .allowlist_function("c_ktx2_transcoder_transcode_slice")
+        .allowlist_function("c_ktx2_transcoder_prepare_animation")
+        .allowlist_function("c_ktx2_transcoder_transcode_frame")
+        .allowlist_function("c_ktx2_transcoder_get_key_values")
+        .allowlist_function("c_ktx2_transcoder_get_key")
+        .allowlist_function("c_ktx2_transcoder_get_key_len")
+        .allowlist_function("c_ktx2_transcoder_get_value")
+        .allowlist_function("c_ktx2_transcoder_get_value_len")
         .allowlist_function("c_ktx2_transcoder_get_r_dst_buf_len")
         .allowlist_function("c_ktx2_transcoder_get_r_width")
         .allowlist_function("c_ktx2_transcoder_get_r_height")
@@ -67,10 +105,23 @@ fn bindgen() {
         .allowlist_function("c_ktx2_transcoder_get_r_faces")
         .allowlist_function("c_ktx2_transcoder_get_r_target_format")
         .allowlist_function("c_ktx2_transcoder_get_r_is_srgb")
+        .allowlist_function("c_ktx2_transcoder_get_r_dfd_channel_id0")
+        .allowlist_function("c_ktx2_transcoder_get_r_dfd_channel_id1")
+        .allowlist_function("c_ktx2_transcoder_get_r_has_alpha_slices")
+        .allowlist_function("c_ktx2_transcoder_get_r_basis_format")
+        .allowlist_function("c_ktx2_transcoder_get_r_error")
+        .allowlist_function("c_ktx2_transcoder_get_r_error_level")
+        .allowlist_function("c_ktx2_transcoder_get_r_error_layer")
+        .allowlist_function("c_ktx2_transcoder_get_r_error_face")
+        .allowlist_function("c_ktx2_transcoder_get_r_error_width")
+        .allowlist_function("c_ktx2_transcoder_get_r_error_height")
         .opaque_type("Transcoder")
-        .bitfield_enum("TextureCompressionMethod")
+        .opaque_type("TranscoderState")
+        .bitfield_enum("DecodeFlags")
+        .bitfield_enum("CompiledTranscodeFormats")
         .newtype_enum("TextureTranscodedFormat")
-        .newtype_enum("ChannelType")
+        .newtype_enum("BasisTexFormat")
+        .newtype_enum("TranscodeErrorCode")
         .generate()
         .expect("Unable to generate bindings")
         .write_to_file(binding_file)
@@ -91,7 +142,25 @@ fn compile_basisu_static() {
     for (define, value) in DEFINES {
         build.define(define, value);
     }
-    build.files(&SRCS).compile("basisu_vendor");
+    for (format, defines) in FORMAT_DEFINES {
+        let value = if feature_enabled(format) { "1" } else { "0" };
+        for define in defines {
+            build.define(define, value);
+        }
+    }
+    let zstd = feature_enabled("zstd");
+    build
+        .files(
+            SRCS.iter()
+                .filter(|src| zstd || !src.ends_with("zstddeclib.c")),
+        )
+        .compile("basisu_vendor");
+}
+
+/// Whether a cargo feature of this crate is enabled.
+fn feature_enabled(feature: &str) -> bool {
+    let var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+    std::env::var_os(var).is_some()
 }
 
 fn gen_wasm_build_cmd() {
@@ -102,7 +171,7 @@ fn gen_wasm_build_cmd() {
         "-sINCOMING_MODULE_JS_API=wasmBinary",
         "-sALLOW_MEMORY_GROWTH",
         "-sEXPORTED_RUNTIME_METHODS=HEAPU8",
-        "-sEXPORTED_FUNCTIONS=_malloc,_free,_c_basisu_transcoder_init,_c_ktx2_transcoder_new,_c_ktx2_transcoder_delete,_c_ktx2_transcoder_transcode_image,_c_ktx2_transcoder_get_r_dst_buf,_c_ktx2_transcoder_get_r_dst_buf_len,_c_ktx2_transcoder_get_r_width,_c_ktx2_transcoder_get_r_height,_c_ktx2_transcoder_get_r_levels,_c_ktx2_transcoder_get_r_layers,_c_ktx2_transcoder_get_r_faces,_c_ktx2_transcoder_get_r_target_format,_c_ktx2_transcoder_get_r_is_srgb",
+        "-sEXPORTED_FUNCTIONS=_malloc,_free,_c_basisu_transcoder_init,_c_basisu_get_compiled_formats,_c_ktx2_transcoder_new,_c_ktx2_transcoder_delete,_c_ktx2_transcoder_transcode_image,_c_basis_transcoder_init,_c_basis_transcoder_transcode_image,_c_ktx2_transcoder_alloc_src_buf,_c_ktx2_transcoder_init,_c_ktx2_transcoder_prepare,_c_ktx2_transcoder_prepare_slices,_c_ktx2_transcoder_get_slices,_c_ktx2_transcoder_get_slice_offset,_c_ktx2_transcoder_state_new,_c_ktx2_transcoder_state_delete,_c_ktx2_transcoder_transcode_slice,_c_ktx2_transcoder_prepare_animation,_c_ktx2_transcoder_transcode_frame,_c_ktx2_transcoder_get_key_values,_c_ktx2_transcoder_get_key,_c_ktx2_transcoder_get_key_len,_c_ktx2_transcoder_get_value,_c_ktx2_transcoder_get_value_len,_c_ktx2_transcoder_get_r_dst_buf,_c_ktx2_transcoder_get_r_dst_buf_len,_c_ktx2_transcoder_get_r_width,_c_ktx2_transcoder_get_r_height,_c_ktx2_transcoder_get_r_levels,_c_ktx2_transcoder_get_r_layers,_c_ktx2_transcoder_get_r_faces,_c_ktx2_transcoder_get_r_target_format,_c_ktx2_transcoder_get_r_is_srgb,_c_ktx2_transcoder_get_r_dfd_channel_id0,_c_ktx2_transcoder_get_r_dfd_channel_id1,_c_ktx2_transcoder_get_r_has_alpha_slices,_c_ktx2_transcoder_get_r_basis_format,_c_ktx2_transcoder_get_r_error,_c_ktx2_transcoder_get_r_error_level,_c_ktx2_transcoder_get_r_error_layer,_c_ktx2_transcoder_get_r_error_face,_c_ktx2_transcoder_get_r_error_width,_c_ktx2_transcoder_get_r_error_height,_c_ktx2_transcoder_set_decode_flags",
     ];
     let mut cmd = std::process::Command::new("em++");
     cmd.args(["-xc++", "-std=c++17"])
@@ -123,9 +192,15 @@ fn gen_wasm_build_cmd() {
     std::fs::write(
         std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("build_wasm_emcc_args.rs"),
         format!(
-            "const DEFAULT_EMCC_ARGS: [&str; {}] = {:?};",
+            "const DEFAULT_EMCC_ARGS: [&str; {}] = {:?};\nconst FORMAT_DEFINES: [(&str, &[&str]); {}] = [{}];",
             default_emcc_args.len(),
-            default_emcc_args
+            default_emcc_args,
+            FORMAT_DEFINES.len(),
+            FORMAT_DEFINES
+                .iter()
+                .map(|(format, defines)| format!("({format:?}, &{defines:?})"))
+                .collect::<Vec<_>>()
+                .join(", ")
         ),
     )
     .unwrap();
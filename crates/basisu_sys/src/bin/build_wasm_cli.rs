@@ -11,16 +11,35 @@ struct Args {
     /// Enable wasm-opt and pass extra flags to it.
     #[arg(long)]
     wasm_opt_flags: Option<String>,
+    /// The optional formats to compile, all by default. ETC1S and the uncompressed targets are always compiled.
+    #[arg(long, value_delimiter = ',', value_parser = FORMAT_DEFINES.map(|(format, _)| format))]
+    formats: Option<Vec<String>>,
 }
 
 pub fn build_wasm_cmd() {
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
     std::env::set_current_dir(manifest_dir).unwrap();
 
+    let user_args = Args::parse();
+    let formats = user_args.formats.unwrap_or_else(|| {
+        FORMAT_DEFINES
+            .iter()
+            .map(|(format, _)| format.to_string())
+            .collect()
+    });
+    let enabled = |format: &str| formats.iter().any(|f| f == format);
+
     let mut emcc_cmd = std::process::Command::new("em++");
-    emcc_cmd.args(DEFAULT_EMCC_ARGS);
+    emcc_cmd.args(
+        DEFAULT_EMCC_ARGS
+            .iter()
+            .filter(|arg| enabled("zstd") || !arg.ends_with("zstddeclib.c")),
+    );
+    for (format, defines) in FORMAT_DEFINES {
+        let value = if enabled(format) { 1 } else { 0 };
+        emcc_cmd.args(defines.iter().map(|define| format!("-D{define}={value}")));
+    }
 
-    let user_args = Args::parse();
     if let Some(flags) = user_args.emcc_flags {
         emcc_cmd.args(flags.split(" ").filter(|s| !s.is_empty()));
     }
@@ -17,7 +17,32 @@ mod transcoding {
     include!(concat!(env!("OUT_DIR"), "/transcoding.rs"));
 }
 
-pub use transcoding::{ChannelType, TextureCompressionMethod, TextureTranscodedFormat, Transcoder};
+pub use transcoding::{
+    BasisTexFormat, CompiledTranscodeFormats, DecodeFlags, TextureTranscodedFormat,
+    TranscodeErrorCode, Transcoder, TranscoderState,
+};
+
+mod target;
+mod transcoder;
+
+pub use target::*;
+pub use transcoder::*;
+
+/// Why and where a transcoder function returned false, see [`ktx2_transcoder_get_r_error`].
+///
+/// Only the first error is recorded. The location fields are 0 if they don't apply to the error code.
+/// The basis format and target format are only meaningful once the header was read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TranscodeError {
+    pub code: TranscodeErrorCode,
+    pub basis_format: BasisTexFormat,
+    pub target_format: TextureTranscodedFormat,
+    pub level: u32,
+    pub layer: u32,
+    pub face: u32,
+    pub width: u32,
+    pub height: u32,
+}
 
 #[cfg(not(all(
     target_arch = "wasm32",
@@ -1,44 +1,255 @@
 #![expect(clippy::missing_safety_doc, reason = "TODO")]
 
-use crate::ChannelType;
-use crate::TextureCompressionMethod;
+use core::ops::Range;
+
+use crate::CompiledTranscodeFormats;
 use crate::TextureTranscodedFormat;
+use crate::TranscodeError;
+use crate::TranscodeParams;
 use crate::Transcoder;
 
 pub use crate::transcoding::c_basisu_transcoder_init as basisu_transcoder_init;
 pub use crate::transcoding::c_ktx2_transcoder_delete as ktx2_transcoder_delete;
+pub use crate::transcoding::c_ktx2_transcoder_get_r_basis_format as ktx2_transcoder_get_r_basis_format;
+pub use crate::transcoding::c_ktx2_transcoder_get_r_dfd_channel_id0 as ktx2_transcoder_get_r_dfd_channel_id0;
+pub use crate::transcoding::c_ktx2_transcoder_get_r_dfd_channel_id1 as ktx2_transcoder_get_r_dfd_channel_id1;
 pub use crate::transcoding::c_ktx2_transcoder_get_r_faces as ktx2_transcoder_get_r_faces;
+pub use crate::transcoding::c_ktx2_transcoder_get_r_has_alpha_slices as ktx2_transcoder_get_r_has_alpha_slices;
 pub use crate::transcoding::c_ktx2_transcoder_get_r_height as ktx2_transcoder_get_r_height;
 pub use crate::transcoding::c_ktx2_transcoder_get_r_is_srgb as ktx2_transcoder_get_r_is_srgb;
 pub use crate::transcoding::c_ktx2_transcoder_get_r_layers as ktx2_transcoder_get_r_layers;
 pub use crate::transcoding::c_ktx2_transcoder_get_r_levels as ktx2_transcoder_get_r_levels;
 pub use crate::transcoding::c_ktx2_transcoder_get_r_target_format as ktx2_transcoder_get_r_target_format;
 pub use crate::transcoding::c_ktx2_transcoder_get_r_width as ktx2_transcoder_get_r_width;
+pub use crate::transcoding::c_ktx2_transcoder_get_slice_offset as ktx2_transcoder_get_slice_offset;
+pub use crate::transcoding::c_ktx2_transcoder_get_slices as ktx2_transcoder_get_slices;
 pub use crate::transcoding::c_ktx2_transcoder_new as ktx2_transcoder_new;
+pub use crate::transcoding::c_ktx2_transcoder_prepare as ktx2_transcoder_prepare;
+pub use crate::transcoding::c_ktx2_transcoder_prepare_animation as ktx2_transcoder_prepare_animation;
+pub use crate::transcoding::c_ktx2_transcoder_set_decode_flags as ktx2_transcoder_set_decode_flags;
+pub use crate::transcoding::c_ktx2_transcoder_set_external_dst_buf as ktx2_transcoder_set_external_dst_buf;
+pub use crate::transcoding::c_ktx2_transcoder_state_delete as ktx2_transcoder_state_delete;
+pub use crate::transcoding::c_ktx2_transcoder_state_new as ktx2_transcoder_state_new;
+pub use crate::transcoding::c_ktx2_transcoder_transcode_frame as ktx2_transcoder_transcode_frame;
+pub use crate::transcoding::c_ktx2_transcoder_transcode_slice as ktx2_transcoder_transcode_slice;
+
+/// The formats the transcoder was compiled with, see the `uastc-*`, `target-*` and `zstd` features.
+pub fn compiled_transcode_formats() -> CompiledTranscodeFormats {
+    // SAFETY: Only reads compile time constants.
+    unsafe { crate::transcoding::c_basisu_get_compiled_formats() }
+}
 
 pub unsafe fn ktx2_transcoder_transcode_image(
     transcoder: *mut Transcoder,
     data: Vec<u8>,
-    supported_compressed_formats: TextureCompressionMethod,
-    channel_type_hint: ChannelType,
-    force_transcode_target: TextureTranscodedFormat,
+    target_format: TextureTranscodedFormat,
 ) -> bool {
     unsafe {
         crate::transcoding::c_ktx2_transcoder_transcode_image(
             transcoder,
             data.as_ptr(),
             u32::try_from(data.len()).unwrap(),
-            supported_compressed_formats,
-            channel_type_hint,
-            force_transcode_target,
+            target_format,
         )
     }
 }
 
-pub unsafe fn ktx2_transcoder_get_r_dst_buf(transcoder: *mut Transcoder) -> Vec<u8> {
-    let ptr = unsafe { crate::transcoding::c_ktx2_transcoder_get_r_dst_buf(transcoder) };
-    let len = unsafe { crate::transcoding::c_ktx2_transcoder_get_r_dst_buf_len(transcoder) };
-    let mut ret = vec![0; len as usize];
-    unsafe { std::ptr::copy_nonoverlapping(ptr, ret.as_mut_ptr(), len as usize) };
-    ret
+/// Parses the KTX2 header and data format descriptor, so that the target format can be selected before [`ktx2_transcoder_prepare`].
+///
+/// `data` must stay alive and unchanged until the transcoder is deleted.
+pub unsafe fn ktx2_transcoder_init(transcoder: *mut Transcoder, data: &[u8]) -> bool {
+    unsafe {
+        crate::transcoding::c_ktx2_transcoder_init(
+            transcoder,
+            data.as_ptr(),
+            u32::try_from(data.len()).unwrap(),
+        )
+    }
+}
+
+pub unsafe fn basis_transcoder_transcode_image(
+    transcoder: *mut Transcoder,
+    data: &[u8],
+    target_format: TextureTranscodedFormat,
+    skip_levels: u32,
+    max_size: u32,
+) -> bool {
+    unsafe {
+        crate::transcoding::c_basis_transcoder_transcode_image(
+            transcoder,
+            data.as_ptr(),
+            u32::try_from(data.len()).unwrap(),
+            target_format,
+            skip_levels,
+            max_size,
+        )
+    }
+}
+
+/// Parses the header of a `.basis` file, so that the target format can be selected before [`basis_transcoder_prepare`].
+pub unsafe fn basis_transcoder_init(transcoder: *mut Transcoder, data: &[u8]) -> bool {
+    unsafe {
+        crate::transcoding::c_basis_transcoder_init(
+            transcoder,
+            data.as_ptr(),
+            u32::try_from(data.len()).unwrap(),
+        )
+    }
+}
+
+/// Prepares the transcoder for [`basis_transcoder_transcode`] without transcoding, see [`ktx2_transcoder_set_external_dst_buf`].
+pub unsafe fn basis_transcoder_prepare(
+    transcoder: *mut Transcoder,
+    data: &[u8],
+    target_format: TextureTranscodedFormat,
+    skip_levels: u32,
+    max_size: u32,
+) -> bool {
+    unsafe {
+        crate::transcoding::c_basis_transcoder_prepare(
+            transcoder,
+            data.as_ptr(),
+            u32::try_from(data.len()).unwrap(),
+            target_format,
+            skip_levels,
+            max_size,
+        )
+    }
+}
+
+/// Transcodes all images of a `.basis` file prepared by [`basis_transcoder_prepare`] with the same data.
+pub unsafe fn basis_transcoder_transcode(transcoder: *mut Transcoder, data: &[u8]) -> bool {
+    unsafe {
+        crate::transcoding::c_basis_transcoder_transcode(
+            transcoder,
+            data.as_ptr(),
+            u32::try_from(data.len()).unwrap(),
+        )
+    }
+}
+
+/// Sets the destination buffer of a prepared transcoder, see [`ktx2_transcoder_set_external_dst_buf`].
+///
+/// `dst_buf` must be [`ktx2_transcoder_get_r_dst_buf_len`] bytes long and stay alive until the transcoder is prepared again, deleted or given another buffer.
+pub unsafe fn ktx2_transcoder_set_dst_buf(transcoder: *mut Transcoder, dst_buf: &mut [u8]) {
+    unsafe {
+        assert_eq!(
+            dst_buf.len(),
+            ktx2_transcoder_get_r_dst_buf_len(transcoder) as usize
+        );
+        crate::transcoding::c_ktx2_transcoder_set_dst_buf(transcoder, dst_buf.as_mut_ptr());
+    }
+}
+
+pub unsafe fn ktx2_transcoder_get_r_dst_buf_len(transcoder: *mut Transcoder) -> u32 {
+    unsafe { crate::transcoding::c_ktx2_transcoder_get_r_dst_buf_len(transcoder) }
+}
+
+/// Returns why and where the last transcoder function returned false.
+pub unsafe fn ktx2_transcoder_get_r_error(transcoder: *mut Transcoder) -> TranscodeError {
+    unsafe {
+        TranscodeError {
+            code: crate::transcoding::c_ktx2_transcoder_get_r_error(transcoder),
+            basis_format: crate::transcoding::c_ktx2_transcoder_get_r_basis_format(transcoder),
+            target_format: crate::transcoding::c_ktx2_transcoder_get_r_target_format(transcoder),
+            level: crate::transcoding::c_ktx2_transcoder_get_r_error_level(transcoder),
+            layer: crate::transcoding::c_ktx2_transcoder_get_r_error_layer(transcoder),
+            face: crate::transcoding::c_ktx2_transcoder_get_r_error_face(transcoder),
+            width: crate::transcoding::c_ktx2_transcoder_get_r_error_width(transcoder),
+            height: crate::transcoding::c_ktx2_transcoder_get_r_error_height(transcoder),
+        }
+    }
+}
+
+/// Returns the key/value data of a prepared KTX2 file, in file order.
+pub unsafe fn ktx2_transcoder_get_key_values(
+    transcoder: *mut Transcoder,
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let count = unsafe { crate::transcoding::c_ktx2_transcoder_get_key_values(transcoder) };
+    (0..count)
+        .map(|index| unsafe {
+            let key = copy_bytes(
+                crate::transcoding::c_ktx2_transcoder_get_key(transcoder, index),
+                crate::transcoding::c_ktx2_transcoder_get_key_len(transcoder, index),
+            );
+            let value = copy_bytes(
+                crate::transcoding::c_ktx2_transcoder_get_value(transcoder, index),
+                crate::transcoding::c_ktx2_transcoder_get_value_len(transcoder, index),
+            );
+            (key, value)
+        })
+        .collect()
+}
+
+unsafe fn copy_bytes(ptr: *const u8, len: u32) -> Vec<u8> {
+    if len == 0 {
+        return Vec::new();
+    }
+    unsafe { core::slice::from_raw_parts(ptr, len as usize) }.to_vec()
+}
+
+/// The destination buffer of a [`Ktx2Transcoder`](crate::Ktx2Transcoder).
+///
+/// It's owned by Rust and passed to the transcoder after each prepare, so the transcoded data is moved out without a copy.
+#[derive(Default)]
+pub(crate) struct DstBuf(Vec<u8>);
+
+impl DstBuf {
+    pub(crate) unsafe fn new(transcoder: *mut Transcoder) -> Self {
+        unsafe { crate::transcoding::c_ktx2_transcoder_set_external_dst_buf(transcoder, true) };
+        Self::default()
+    }
+
+    /// Allocates the destination buffer of a prepared transcoder. Returns false if it couldn't be allocated.
+    pub(crate) unsafe fn alloc(&mut self, transcoder: *mut Transcoder) -> bool {
+        let len = unsafe { ktx2_transcoder_get_r_dst_buf_len(transcoder) } as usize;
+        let mut buf = Vec::new();
+        if buf.try_reserve_exact(len).is_err() {
+            return false;
+        }
+        buf.resize(len, 0);
+        self.0 = buf;
+        unsafe { ktx2_transcoder_set_dst_buf(transcoder, &mut self.0) };
+        true
+    }
+
+    /// Selects the target format of a `.basis` file, prepares it, allocates the destination buffer and transcodes into it.
+    pub(crate) unsafe fn transcode_basis(
+        &mut self,
+        transcoder: *mut Transcoder,
+        data: &[u8],
+        params: &TranscodeParams,
+    ) -> bool {
+        unsafe {
+            if !basis_transcoder_init(transcoder, data) {
+                return false;
+            }
+            let source = crate::read_source_format(transcoder, true);
+            basis_transcoder_prepare(
+                transcoder,
+                data,
+                crate::select_target_format(&source, params, crate::compiled_transcode_formats()),
+                params.skip_levels,
+                params.max_size,
+            ) && self.alloc(transcoder)
+                && basis_transcoder_transcode(transcoder, data)
+        }
+    }
+
+    /// Copies a byte range of the buffer, `None` if it's out of bounds.
+    pub(crate) unsafe fn copy(
+        &self,
+        _transcoder: *mut Transcoder,
+        range: Range<usize>,
+    ) -> Option<Vec<u8>> {
+        self.0.get(range).map(<[u8]>::to_vec)
+    }
+
+    /// Moves the buffer out, the transcoder has to be prepared again before transcoding.
+    pub(crate) unsafe fn take(&mut self, transcoder: *mut Transcoder) -> Vec<u8> {
+        unsafe {
+            crate::transcoding::c_ktx2_transcoder_set_dst_buf(transcoder, core::ptr::null_mut())
+        };
+        core::mem::take(&mut self.0)
+    }
 }
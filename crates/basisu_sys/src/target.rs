@@ -0,0 +1,666 @@
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+
+use crate::{BasisTexFormat, CompiledTranscodeFormats, TextureTranscodedFormat, TranscodeParams};
+
+/// The channels of a texture, which decide the target format within a [`TranscodeTarget`].
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct ChannelType(pub u8);
+
+impl ChannelType {
+    pub const CHANNEL_UNDEFINED: ChannelType = ChannelType(0);
+    pub const CHANNEL_RGBA: ChannelType = ChannelType(1);
+    pub const CHANNEL_RGB: ChannelType = ChannelType(2);
+    pub const CHANNEL_RG: ChannelType = ChannelType(3);
+    pub const CHANNEL_R: ChannelType = ChannelType(4);
+}
+
+/// The compressed formats supported by the device.
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct TextureCompressionMethod(pub u8);
+
+impl TextureCompressionMethod {
+    pub const NONE: TextureCompressionMethod = TextureCompressionMethod(0);
+    pub const ASTC_LDR: TextureCompressionMethod = TextureCompressionMethod(1 << 0);
+    pub const ASTC_HDR: TextureCompressionMethod = TextureCompressionMethod(1 << 1);
+    pub const BC: TextureCompressionMethod = TextureCompressionMethod(1 << 2);
+    pub const ETC2: TextureCompressionMethod = TextureCompressionMethod(1 << 3);
+
+    /// Whether all formats of `other` are supported.
+    pub fn contains(self, other: Self) -> bool {
+        self & other == other
+    }
+}
+
+impl BitOr for TextureCompressionMethod {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        TextureCompressionMethod(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for TextureCompressionMethod {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for TextureCompressionMethod {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self {
+        TextureCompressionMethod(self.0 & other.0)
+    }
+}
+
+impl BitAndAssign for TextureCompressionMethod {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+/// The compressed formats compiled into the transcoder, see [`compiled_transcode_formats`](crate::compiled_transcode_formats).
+pub fn compiled_compression_methods() -> TextureCompressionMethod {
+    crate::compiled_transcode_formats().compression_methods()
+}
+
+/// Whether the transcoder was compiled with a basis format, see [`compiled_transcode_formats`](crate::compiled_transcode_formats).
+///
+/// ETC1S is always compiled, unknown basis formats never are.
+pub fn is_basis_format_compiled(basis_format: BasisTexFormat) -> bool {
+    crate::compiled_transcode_formats().has_basis_format(basis_format)
+}
+
+impl CompiledTranscodeFormats {
+    /// Whether all formats of `other` are compiled.
+    pub fn contains(self, other: Self) -> bool {
+        self & other == other
+    }
+
+    /// The compressed formats of the compiled targets.
+    pub fn compression_methods(self) -> TextureCompressionMethod {
+        let mut methods = TextureCompressionMethod::NONE;
+        if self.contains(Self::COMPILED_TRANSCODE_FORMATS_TARGET_ASTC) {
+            methods |= TextureCompressionMethod::ASTC_LDR | TextureCompressionMethod::ASTC_HDR;
+        }
+        if self.contains(Self::COMPILED_TRANSCODE_FORMATS_TARGET_BC) {
+            methods |= TextureCompressionMethod::BC;
+        }
+        if self.contains(Self::COMPILED_TRANSCODE_FORMATS_TARGET_ETC2) {
+            methods |= TextureCompressionMethod::ETC2;
+        }
+        methods
+    }
+
+    /// Whether a basis format is compiled. ETC1S always is, unknown basis formats never are.
+    pub fn has_basis_format(self, basis_format: BasisTexFormat) -> bool {
+        match basis_format {
+            BasisTexFormat::cETC1S => true,
+            BasisTexFormat::cUASTC4x4 => self.contains(Self::COMPILED_TRANSCODE_FORMATS_UASTC_LDR),
+            BasisTexFormat::cUASTC_HDR_4x4
+            | BasisTexFormat::cASTC_HDR_6x6
+            | BasisTexFormat::cASTC_HDR_6x6_INTERMEDIATE => {
+                self.contains(Self::COMPILED_TRANSCODE_FORMATS_UASTC_HDR)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A group of transcode targets, the format within the group depends on the basis format and channel type.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum TranscodeTarget {
+    /// ASTC 4x4 for LDR, ASTC HDR 4x4 or 6x6 for HDR formats.
+    Astc,
+    /// BC7, BC4 or BC5 for LDR, BC6H for HDR formats.
+    Bc,
+    /// ETC1, ETC2, EAC R11 or EAC RG11, LDR formats only.
+    Etc2,
+    /// RGBA32 for LDR, RGBA half for HDR formats.
+    Uncompressed,
+}
+
+/// The maximum number of targets per basis format and channel type in a [`TargetPriority`].
+pub const TARGET_PRIORITY_LEN: usize = 4;
+
+const BASIS_FORMATS: usize = 5;
+const CHANNEL_TYPES: usize = 4;
+
+type TargetList = [Option<TranscodeTarget>; TARGET_PRIORITY_LEN];
+
+/// The transcode targets to try first for each basis format and channel type, before the default priority.
+///
+/// Targets the device doesn't support are skipped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TargetPriority([[TargetList; CHANNEL_TYPES]; BASIS_FORMATS]);
+
+impl TargetPriority {
+    /// Sets the targets to try first for a basis format and channel type, at most [`TARGET_PRIORITY_LEN`] are used.
+    ///
+    /// # Panics
+    ///
+    /// If the basis format is unknown or the channel type is [`ChannelType::CHANNEL_UNDEFINED`].
+    pub fn set(
+        &mut self,
+        basis_format: BasisTexFormat,
+        channel_type: ChannelType,
+        targets: &[TranscodeTarget],
+    ) {
+        let list = &mut self.0[basis_format.0 as usize][usize::from(channel_type.0) - 1];
+        *list = [None; TARGET_PRIORITY_LEN];
+        for (slot, &target) in list.iter_mut().zip(targets) {
+            *slot = Some(target);
+        }
+    }
+
+    /// The targets to try first for a basis format and channel type, none if either is unknown.
+    pub fn targets(
+        &self,
+        basis_format: BasisTexFormat,
+        channel_type: ChannelType,
+    ) -> impl Iterator<Item = TranscodeTarget> + '_ {
+        usize::from(channel_type.0)
+            .checked_sub(1)
+            .and_then(|channel_index| self.0.get(basis_format.0 as usize)?.get(channel_index))
+            .into_iter()
+            .flatten()
+            .map_while(|&target| target)
+    }
+}
+
+// Channel ids of the KTX2 data format descriptor, see `ktx2_df_channel_id` in basisu_transcoder.h.
+const DFD_CHANNEL_UASTC_RGB: u32 = 0;
+const DFD_CHANNEL_UASTC_RGBA: u32 = 3;
+const DFD_CHANNEL_UASTC_RRR: u32 = 4;
+const DFD_CHANNEL_UASTC_RRRG: u32 = 5;
+const DFD_CHANNEL_UASTC_RG: u32 = 6;
+const DFD_CHANNEL_ETC1S_RGB: u32 = 0;
+const DFD_CHANNEL_ETC1S_RRR: u32 = 3;
+const DFD_CHANNEL_ETC1S_GGG: u32 = 4;
+const DFD_CHANNEL_ETC1S_AAA: u32 = 15;
+
+/// Where the channels of a texture are read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceChannels {
+    /// The channel ids of the first two samples of a KTX2 data format descriptor.
+    Dfd { channel_id0: u32, channel_id1: u32 },
+    /// `.basis` files have no data format descriptor, only whether they have alpha slices.
+    Basis { has_alpha_slices: bool },
+}
+
+/// What a KTX2 or `.basis` file reports before a target format is selected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceFormat {
+    pub basis_format: BasisTexFormat,
+    pub is_srgb: bool,
+    pub channels: SourceChannels,
+}
+
+impl SourceFormat {
+    /// The channel type of the texture, RGBA if the channels are unknown.
+    pub fn channel_type(&self) -> ChannelType {
+        match self.channels {
+            SourceChannels::Basis { has_alpha_slices } => {
+                if has_alpha_slices {
+                    ChannelType::CHANNEL_RGBA
+                } else {
+                    ChannelType::CHANNEL_RGB
+                }
+            }
+            SourceChannels::Dfd { channel_id0, .. }
+                if self.basis_format == BasisTexFormat::cUASTC4x4 =>
+            {
+                match channel_id0 {
+                    DFD_CHANNEL_UASTC_RGB => ChannelType::CHANNEL_RGB,
+                    DFD_CHANNEL_UASTC_RGBA => ChannelType::CHANNEL_RGBA,
+                    DFD_CHANNEL_UASTC_RRR => ChannelType::CHANNEL_R,
+                    DFD_CHANNEL_UASTC_RRRG | DFD_CHANNEL_UASTC_RG => ChannelType::CHANNEL_RG,
+                    _ => ChannelType::CHANNEL_RGBA,
+                }
+            }
+            SourceChannels::Dfd {
+                channel_id0,
+                channel_id1,
+            } => match (channel_id0, channel_id1) {
+                (DFD_CHANNEL_ETC1S_RGB, DFD_CHANNEL_ETC1S_AAA) => ChannelType::CHANNEL_RGBA,
+                (DFD_CHANNEL_ETC1S_RGB, _) => ChannelType::CHANNEL_RGB,
+                (DFD_CHANNEL_ETC1S_RRR, DFD_CHANNEL_ETC1S_GGG) => ChannelType::CHANNEL_RG,
+                (DFD_CHANNEL_ETC1S_RRR, _) => ChannelType::CHANNEL_R,
+                _ => ChannelType::CHANNEL_RGBA,
+            },
+        }
+    }
+}
+
+/// Selects the target format of a texture according to <https://github.com/KhronosGroup/3D-Formats-Guidelines/blob/main/KTXDeveloperGuide.md>,
+/// after the targets of [`TranscodeParams::target_priority`].
+///
+/// Targets that aren't in `compiled`, usually [`compiled_transcode_formats`](crate::compiled_transcode_formats), are skipped.
+///
+/// Returns [`TextureTranscodedFormat::cTFTotalTextureFormats`] if no target is supported, e.g. for unknown basis formats
+/// or ones that aren't in `compiled`.
+pub fn select_target_format(
+    source: &SourceFormat,
+    params: &TranscodeParams,
+    compiled: CompiledTranscodeFormats,
+) -> TextureTranscodedFormat {
+    if params.force_transcode_target != TextureTranscodedFormat::cTFTotalTextureFormats {
+        return params.force_transcode_target;
+    }
+    if !compiled.has_basis_format(source.basis_format) {
+        return TextureTranscodedFormat::cTFTotalTextureFormats;
+    }
+    let supported_compressed_formats =
+        params.supported_compressed_formats & compiled.compression_methods();
+    let channel_type = if params.channel_type_hint != ChannelType::CHANNEL_UNDEFINED {
+        params.channel_type_hint
+    } else {
+        source.channel_type()
+    };
+    params
+        .target_priority
+        .targets(source.basis_format, channel_type)
+        .chain(default_priority(source.basis_format).iter().copied())
+        .find_map(|target| {
+            target_group_format(
+                target,
+                source.basis_format,
+                channel_type,
+                supported_compressed_formats,
+            )
+        })
+        .unwrap_or(TextureTranscodedFormat::cTFTotalTextureFormats)
+}
+
+fn default_priority(basis_format: BasisTexFormat) -> &'static [TranscodeTarget] {
+    match basis_format {
+        // Prefer BC7 over ETC2 for ETC1S because on some desktop platforms ETC2 is really slow.
+        BasisTexFormat::cETC1S => &[
+            TranscodeTarget::Bc,
+            TranscodeTarget::Etc2,
+            TranscodeTarget::Uncompressed,
+        ],
+        BasisTexFormat::cUASTC4x4 => &[
+            TranscodeTarget::Astc,
+            TranscodeTarget::Bc,
+            TranscodeTarget::Etc2,
+            TranscodeTarget::Uncompressed,
+        ],
+        BasisTexFormat::cUASTC_HDR_4x4
+        | BasisTexFormat::cASTC_HDR_6x6
+        | BasisTexFormat::cASTC_HDR_6x6_INTERMEDIATE => &[
+            TranscodeTarget::Astc,
+            TranscodeTarget::Bc,
+            TranscodeTarget::Uncompressed,
+        ],
+        _ => &[],
+    }
+}
+
+/// The format of a target group for the basis format and channel type, or `None` if the device doesn't support it.
+fn target_group_format(
+    target: TranscodeTarget,
+    basis_format: BasisTexFormat,
+    channel_type: ChannelType,
+    supported_compressed_formats: TextureCompressionMethod,
+) -> Option<TextureTranscodedFormat> {
+    let is_etc1s = basis_format == BasisTexFormat::cETC1S;
+    let is_hdr = !is_etc1s && basis_format != BasisTexFormat::cUASTC4x4;
+    let (format, required) = match target {
+        TranscodeTarget::Astc => {
+            let format = if basis_format == BasisTexFormat::cUASTC_HDR_4x4 {
+                TextureTranscodedFormat::cTFASTC_HDR_4x4_RGBA
+            } else if is_hdr {
+                TextureTranscodedFormat::cTFASTC_HDR_6x6_RGBA
+            } else {
+                TextureTranscodedFormat::cTFASTC_4x4_RGBA
+            };
+            let required = if is_hdr {
+                TextureCompressionMethod::ASTC_HDR
+            } else {
+                TextureCompressionMethod::ASTC_LDR
+            };
+            (format, required)
+        }
+        TranscodeTarget::Bc => {
+            let format = match channel_type {
+                _ if is_hdr => TextureTranscodedFormat::cTFBC6H,
+                ChannelType::CHANNEL_R if is_etc1s => TextureTranscodedFormat::cTFBC4_R,
+                ChannelType::CHANNEL_RG if is_etc1s => TextureTranscodedFormat::cTFBC5_RG,
+                _ => TextureTranscodedFormat::cTFBC7_RGBA,
+            };
+            (format, TextureCompressionMethod::BC)
+        }
+        TranscodeTarget::Etc2 if is_hdr => return None,
+        TranscodeTarget::Etc2 => {
+            let format = match channel_type {
+                ChannelType::CHANNEL_RGB => TextureTranscodedFormat::cTFETC1_RGB,
+                ChannelType::CHANNEL_R => TextureTranscodedFormat::cTFETC2_EAC_R11,
+                ChannelType::CHANNEL_RG => TextureTranscodedFormat::cTFETC2_EAC_RG11,
+                _ => TextureTranscodedFormat::cTFETC2_RGBA,
+            };
+            (format, TextureCompressionMethod::ETC2)
+        }
+        TranscodeTarget::Uncompressed => {
+            let format = if is_hdr {
+                TextureTranscodedFormat::cTFRGBA_HALF
+            } else {
+                TextureTranscodedFormat::cTFRGBA32
+            };
+            (format, TextureCompressionMethod::NONE)
+        }
+    };
+    supported_compressed_formats
+        .contains(required)
+        .then_some(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: TextureCompressionMethod = TextureCompressionMethod(0b1111);
+    const ALL_COMPILED: CompiledTranscodeFormats = CompiledTranscodeFormats(0b11_1111);
+
+    fn dfd(basis_format: BasisTexFormat, channel_id0: u32, channel_id1: u32) -> SourceFormat {
+        SourceFormat {
+            basis_format,
+            is_srgb: false,
+            channels: SourceChannels::Dfd {
+                channel_id0,
+                channel_id1,
+            },
+        }
+    }
+
+    fn basis(basis_format: BasisTexFormat, has_alpha_slices: bool) -> SourceFormat {
+        SourceFormat {
+            basis_format,
+            is_srgb: false,
+            channels: SourceChannels::Basis { has_alpha_slices },
+        }
+    }
+
+    #[test]
+    fn channel_type() {
+        let cases = [
+            (dfd(BasisTexFormat::cETC1S, 0, 0), ChannelType::CHANNEL_RGB),
+            (
+                dfd(BasisTexFormat::cETC1S, 0, 15),
+                ChannelType::CHANNEL_RGBA,
+            ),
+            (dfd(BasisTexFormat::cETC1S, 3, 0), ChannelType::CHANNEL_R),
+            (dfd(BasisTexFormat::cETC1S, 3, 4), ChannelType::CHANNEL_RG),
+            (dfd(BasisTexFormat::cETC1S, 7, 0), ChannelType::CHANNEL_RGBA),
+            (
+                dfd(BasisTexFormat::cUASTC4x4, 0, 0),
+                ChannelType::CHANNEL_RGB,
+            ),
+            (
+                dfd(BasisTexFormat::cUASTC4x4, 3, 0),
+                ChannelType::CHANNEL_RGBA,
+            ),
+            (dfd(BasisTexFormat::cUASTC4x4, 4, 0), ChannelType::CHANNEL_R),
+            (
+                dfd(BasisTexFormat::cUASTC4x4, 5, 0),
+                ChannelType::CHANNEL_RG,
+            ),
+            (
+                dfd(BasisTexFormat::cUASTC4x4, 6, 0),
+                ChannelType::CHANNEL_RG,
+            ),
+            (
+                dfd(BasisTexFormat::cUASTC4x4, 9, 0),
+                ChannelType::CHANNEL_RGBA,
+            ),
+            (
+                basis(BasisTexFormat::cETC1S, false),
+                ChannelType::CHANNEL_RGB,
+            ),
+            (
+                basis(BasisTexFormat::cUASTC4x4, true),
+                ChannelType::CHANNEL_RGBA,
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(source.channel_type(), expected, "{source:?}");
+        }
+    }
+
+    #[test]
+    fn default_selection() {
+        use TextureCompressionMethod as M;
+        use TextureTranscodedFormat as F;
+        let etc1s_rgb = dfd(BasisTexFormat::cETC1S, 0, 0);
+        let etc1s_rgba = dfd(BasisTexFormat::cETC1S, 0, 15);
+        let etc1s_r = dfd(BasisTexFormat::cETC1S, 3, 0);
+        let etc1s_rg = dfd(BasisTexFormat::cETC1S, 3, 4);
+        let uastc_rgb = dfd(BasisTexFormat::cUASTC4x4, 0, 0);
+        let uastc_rg = dfd(BasisTexFormat::cUASTC4x4, 6, 0);
+        let hdr_4x4 = dfd(BasisTexFormat::cUASTC_HDR_4x4, 0, 0);
+        let hdr_6x6 = dfd(BasisTexFormat::cASTC_HDR_6x6, 0, 0);
+        let hdr_6x6_intermediate = dfd(BasisTexFormat::cASTC_HDR_6x6_INTERMEDIATE, 0, 0);
+        let cases = [
+            (etc1s_rgb, ALL, F::cTFBC7_RGBA),
+            (etc1s_rgba, ALL, F::cTFBC7_RGBA),
+            (etc1s_r, ALL, F::cTFBC4_R),
+            (etc1s_rg, ALL, F::cTFBC5_RG),
+            (etc1s_rgb, M::ASTC_LDR | M::ETC2, F::cTFETC1_RGB),
+            (etc1s_rgba, M::ETC2, F::cTFETC2_RGBA),
+            (etc1s_r, M::ETC2, F::cTFETC2_EAC_R11),
+            (etc1s_rg, M::ETC2, F::cTFETC2_EAC_RG11),
+            (etc1s_rgba, M::ASTC_LDR, F::cTFRGBA32),
+            (uastc_rgb, ALL, F::cTFASTC_4x4_RGBA),
+            (uastc_rgb, M::BC | M::ETC2, F::cTFBC7_RGBA),
+            (uastc_rg, M::BC, F::cTFBC7_RGBA),
+            (uastc_rg, M::ETC2, F::cTFETC2_EAC_RG11),
+            (uastc_rgb, M::ASTC_HDR, F::cTFRGBA32),
+            (uastc_rgb, M::NONE, F::cTFRGBA32),
+            (hdr_4x4, ALL, F::cTFASTC_HDR_4x4_RGBA),
+            (hdr_4x4, M::ASTC_LDR | M::BC, F::cTFBC6H),
+            (hdr_4x4, M::ETC2, F::cTFRGBA_HALF),
+            (hdr_6x6, ALL, F::cTFASTC_HDR_6x6_RGBA),
+            (hdr_6x6_intermediate, M::ASTC_HDR, F::cTFASTC_HDR_6x6_RGBA),
+            (hdr_6x6, M::BC, F::cTFBC6H),
+            (hdr_6x6, M::NONE, F::cTFRGBA_HALF),
+            (dfd(BasisTexFormat(9), 0, 0), ALL, F::cTFTotalTextureFormats),
+        ];
+        for (source, supported_compressed_formats, expected) in cases {
+            let params = TranscodeParams {
+                supported_compressed_formats,
+                ..Default::default()
+            };
+            assert_eq!(
+                select_target_format(&source, &params, ALL_COMPILED),
+                expected,
+                "{source:?} {supported_compressed_formats:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn compiled_out_targets() {
+        use CompiledTranscodeFormats as C;
+        use TextureTranscodedFormat as F;
+        let etc1s_rgb = dfd(BasisTexFormat::cETC1S, 0, 0);
+        let etc1s_rgba = dfd(BasisTexFormat::cETC1S, 0, 15);
+        let uastc_rgb = dfd(BasisTexFormat::cUASTC4x4, 0, 0);
+        let hdr_4x4 = dfd(BasisTexFormat::cUASTC_HDR_4x4, 0, 0);
+        let cases = [
+            // BC isn't compiled, so the next target of the priority is used.
+            (
+                etc1s_rgba,
+                C::COMPILED_TRANSCODE_FORMATS_TARGET_ETC2,
+                F::cTFETC2_RGBA,
+            ),
+            (
+                uastc_rgb,
+                C::COMPILED_TRANSCODE_FORMATS_UASTC_LDR | C::COMPILED_TRANSCODE_FORMATS_TARGET_BC,
+                F::cTFBC7_RGBA,
+            ),
+            (
+                hdr_4x4,
+                C::COMPILED_TRANSCODE_FORMATS_UASTC_HDR,
+                F::cTFRGBA_HALF,
+            ),
+            // ETC1S and the uncompressed targets are always compiled.
+            (etc1s_rgb, C::COMPILED_TRANSCODE_FORMATS_NONE, F::cTFRGBA32),
+            // Basis formats that aren't compiled have no target.
+            (
+                uastc_rgb,
+                C::COMPILED_TRANSCODE_FORMATS_TARGET_BC,
+                F::cTFTotalTextureFormats,
+            ),
+            (
+                hdr_4x4,
+                C::COMPILED_TRANSCODE_FORMATS_UASTC_LDR,
+                F::cTFTotalTextureFormats,
+            ),
+        ];
+        let params = TranscodeParams {
+            supported_compressed_formats: ALL,
+            ..Default::default()
+        };
+        for (source, compiled, expected) in cases {
+            assert_eq!(
+                select_target_format(&source, &params, compiled),
+                expected,
+                "{source:?} {compiled:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn params() {
+        use TextureTranscodedFormat as F;
+        let etc1s_rgba = dfd(BasisTexFormat::cETC1S, 0, 15);
+        let hdr_4x4 = dfd(BasisTexFormat::cUASTC_HDR_4x4, 0, 0);
+        let mut etc1s_astc = TargetPriority::default();
+        etc1s_astc.set(
+            BasisTexFormat::cETC1S,
+            ChannelType::CHANNEL_RGBA,
+            &[TranscodeTarget::Astc, TranscodeTarget::Etc2],
+        );
+        let mut hdr_etc2 = TargetPriority::default();
+        hdr_etc2.set(
+            BasisTexFormat::cUASTC_HDR_4x4,
+            ChannelType::CHANNEL_RGB,
+            &[TranscodeTarget::Etc2, TranscodeTarget::Uncompressed],
+        );
+        let all = TranscodeParams {
+            supported_compressed_formats: ALL,
+            ..Default::default()
+        };
+        let cases = [
+            // A forced target is used as is.
+            (
+                etc1s_rgba,
+                TranscodeParams {
+                    force_transcode_target: F::cTFPVRTC1_4_RGBA,
+                    ..all
+                },
+                F::cTFPVRTC1_4_RGBA,
+            ),
+            // The hint overrides the channels of the texture.
+            (
+                etc1s_rgba,
+                TranscodeParams {
+                    channel_type_hint: ChannelType::CHANNEL_R,
+                    ..all
+                },
+                F::cTFBC4_R,
+            ),
+            (
+                etc1s_rgba,
+                TranscodeParams {
+                    target_priority: etc1s_astc,
+                    ..all
+                },
+                F::cTFASTC_4x4_RGBA,
+            ),
+            // The priority only applies to its channel type.
+            (
+                etc1s_rgba,
+                TranscodeParams {
+                    channel_type_hint: ChannelType::CHANNEL_RGB,
+                    target_priority: etc1s_astc,
+                    ..all
+                },
+                F::cTFBC7_RGBA,
+            ),
+            // Unsupported targets are skipped.
+            (
+                etc1s_rgba,
+                TranscodeParams {
+                    supported_compressed_formats: TextureCompressionMethod::BC
+                        | TextureCompressionMethod::ETC2,
+                    target_priority: etc1s_astc,
+                    ..all
+                },
+                F::cTFETC2_RGBA,
+            ),
+            // ETC2 has no HDR formats.
+            (
+                hdr_4x4,
+                TranscodeParams {
+                    target_priority: hdr_etc2,
+                    ..all
+                },
+                F::cTFRGBA_HALF,
+            ),
+        ];
+        for (source, params, expected) in cases {
+            assert_eq!(
+                select_target_format(&source, &params, ALL_COMPILED),
+                expected,
+                "{source:?} {params:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn target_priority() {
+        let mut priority = TargetPriority::default();
+        let targets = [
+            TranscodeTarget::Uncompressed,
+            TranscodeTarget::Bc,
+            TranscodeTarget::Astc,
+            TranscodeTarget::Etc2,
+            TranscodeTarget::Bc,
+        ];
+        priority.set(BasisTexFormat::cUASTC4x4, ChannelType::CHANNEL_R, &targets);
+        assert!(
+            priority
+                .targets(BasisTexFormat::cUASTC4x4, ChannelType::CHANNEL_R)
+                .eq(targets[..TARGET_PRIORITY_LEN].iter().copied())
+        );
+        priority.set(
+            BasisTexFormat::cUASTC4x4,
+            ChannelType::CHANNEL_R,
+            &[TranscodeTarget::Etc2],
+        );
+        assert!(
+            priority
+                .targets(BasisTexFormat::cUASTC4x4, ChannelType::CHANNEL_R)
+                .eq([TranscodeTarget::Etc2])
+        );
+        assert_eq!(
+            priority
+                .targets(BasisTexFormat::cUASTC4x4, ChannelType::CHANNEL_RG)
+                .count(),
+            0
+        );
+        assert_eq!(
+            priority
+                .targets(BasisTexFormat::cUASTC4x4, ChannelType::CHANNEL_UNDEFINED)
+                .count(),
+            0
+        );
+        assert_eq!(
+            priority
+                .targets(BasisTexFormat(9), ChannelType::CHANNEL_R)
+                .count(),
+            0
+        );
+    }
+}
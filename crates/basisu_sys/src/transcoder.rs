@@ -0,0 +1,628 @@
+use core::fmt;
+use core::ops::Range;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::{
+    BasisTexFormat, ChannelType, DecodeFlags, DstBuf, SourceChannels, SourceFormat, TargetPriority,
+    TextureCompressionMethod, TextureTranscodedFormat, TranscodeError, TranscodeErrorCode,
+    Transcoder, TranscoderState,
+};
+
+/// How a texture is transcoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TranscodeParams {
+    /// The compressed formats supported by the device, the target format is selected among them.
+    pub supported_compressed_formats: TextureCompressionMethod,
+    /// The channel type hint for target selection, [`ChannelType::CHANNEL_UNDEFINED`] to read it from the texture.
+    pub channel_type_hint: ChannelType,
+    /// The target format, or [`TextureTranscodedFormat::cTFTotalTextureFormats`] to select it automatically.
+    pub force_transcode_target: TextureTranscodedFormat,
+    /// Skip this many of the largest mip levels. The smallest level is always kept.
+    pub skip_levels: u32,
+    /// Skip the mip levels larger than this in width or height, or 0 to not limit the size. The smallest level is always kept.
+    pub max_size: u32,
+    /// The decode flags passed to Basis Universal for every transcoded image.
+    pub decode_flags: DecodeFlags,
+    /// The transcode targets to try first when the target is selected automatically, see [`select_target_format`](crate::select_target_format).
+    pub target_priority: TargetPriority,
+}
+
+impl Default for TranscodeParams {
+    fn default() -> Self {
+        Self {
+            supported_compressed_formats: TextureCompressionMethod::NONE,
+            channel_type_hint: ChannelType::CHANNEL_UNDEFINED,
+            force_transcode_target: TextureTranscodedFormat::cTFTotalTextureFormats,
+            skip_levels: 0,
+            max_size: 0,
+            decode_flags: DecodeFlags::DECODE_FLAGS_NONE,
+            target_priority: TargetPriority::default(),
+        }
+    }
+}
+
+/// The layout of a prepared or transcoded texture.
+///
+/// The transcoded data is ordered by level, then layer, then face. `layers` is 0 if the texture isn't an array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureLayout {
+    pub basis_format: BasisTexFormat,
+    pub target_format: TextureTranscodedFormat,
+    pub is_srgb: bool,
+    pub width: u32,
+    pub height: u32,
+    pub levels: u32,
+    pub layers: u32,
+    pub faces: u32,
+}
+
+/// A transcoded texture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscodedTexture {
+    pub layout: TextureLayout,
+    pub data: Vec<u8>,
+}
+
+/// A Basis Universal transcoder for KTX2 and `.basis` files.
+///
+/// KTX2 files are first prepared, then their slices (a level, layer and face each) are transcoded, possibly in parallel from multiple threads with a [`TranscodeState`] each.
+pub struct Ktx2Transcoder {
+    raw: NonNull<Transcoder>,
+    /// The transcoder keeps referencing the prepared data on native platforms.
+    data: Option<Arc<[u8]>>,
+    layout: Option<TextureLayout>,
+    animation: bool,
+    /// Whether each slice was transcoded, so that no two threads write the same slice.
+    transcoded_slices: Box<[AtomicBool]>,
+    /// On native platforms the slices are transcoded straight into this buffer.
+    dst_buf: DstBuf,
+    /// Whether the destination buffer of the prepared texture was allocated, see [`Ktx2Transcoder::alloc_dst_buf`].
+    dst_buf_allocated: bool,
+    /// The parameters of the last prepare, which the Web Workers prepare their own transcoders with.
+    #[cfg(all(
+        target_arch = "wasm32",
+        target_vendor = "unknown",
+        target_os = "unknown",
+    ))]
+    params: TranscodeParams,
+    /// The id of the prepared data in the Web Workers, which keep it until it's released.
+    #[cfg(all(
+        target_arch = "wasm32",
+        target_vendor = "unknown",
+        target_os = "unknown",
+    ))]
+    worker_texture: u64,
+}
+
+// SAFETY: The transcoder owns its buffers and the data it references.
+unsafe impl Send for Ktx2Transcoder {}
+// SAFETY: Methods taking `&self` only read the prepared layout, record errors behind a mutex or transcode slices that are claimed by a single thread.
+unsafe impl Sync for Ktx2Transcoder {}
+
+impl Ktx2Transcoder {
+    pub fn new() -> Result<Self, TranscodeError> {
+        // SAFETY: Creating a transcoder has no preconditions.
+        let raw = NonNull::new(unsafe { crate::ktx2_transcoder_new() }).ok_or_else(|| {
+            error_without_transcoder(TranscodeErrorCode::TRANSCODE_ERROR_OUT_OF_MEMORY)
+        })?;
+        Ok(Self {
+            raw,
+            data: None,
+            layout: None,
+            animation: false,
+            transcoded_slices: Box::new([]),
+            // SAFETY: The transcoder was just created.
+            dst_buf: unsafe { DstBuf::new(raw.as_ptr()) },
+            dst_buf_allocated: false,
+            #[cfg(all(
+                target_arch = "wasm32",
+                target_vendor = "unknown",
+                target_os = "unknown",
+            ))]
+            params: TranscodeParams::default(),
+            #[cfg(all(
+                target_arch = "wasm32",
+                target_vendor = "unknown",
+                target_os = "unknown",
+            ))]
+            worker_texture: 0,
+        })
+    }
+
+    /// Prepares a KTX2 file for [`Self::transcode_slice`].
+    pub fn prepare(
+        &mut self,
+        data: Arc<[u8]>,
+        params: &TranscodeParams,
+    ) -> Result<TextureLayout, TranscodeError> {
+        self.prepare_impl(data, params, false, true)
+    }
+
+    /// Prepares a KTX2 file like [`Self::prepare`], without allocating the destination buffer on native platforms.
+    ///
+    /// E.g. to look up an already transcoded copy of the texture first. Call [`Self::alloc_dst_buf`] before transcoding.
+    pub fn prepare_without_dst_buf(
+        &mut self,
+        data: Arc<[u8]>,
+        params: &TranscodeParams,
+    ) -> Result<TextureLayout, TranscodeError> {
+        self.prepare_impl(data, params, false, false)
+    }
+
+    /// Prepares an animated KTX2 file for [`Self::transcode_frame`]. The destination buffer only holds a single frame.
+    pub fn prepare_animation(
+        &mut self,
+        data: Arc<[u8]>,
+        params: &TranscodeParams,
+    ) -> Result<TextureLayout, TranscodeError> {
+        self.prepare_impl(data, params, true, true)
+    }
+
+    fn prepare_impl(
+        &mut self,
+        data: Arc<[u8]>,
+        params: &TranscodeParams,
+        animation: bool,
+        alloc_dst_buf: bool,
+    ) -> Result<TextureLayout, TranscodeError> {
+        self.layout = None;
+        self.transcoded_slices = Box::new([]);
+        self.dst_buf_allocated = false;
+        // SAFETY: `data` is kept alive by `self` until the transcoder is prepared again or deleted.
+        let initialized = unsafe { crate::ktx2_transcoder_init(self.raw.as_ptr(), &data) };
+        self.data = Some(data);
+        if !initialized {
+            return Err(self.error());
+        }
+        // SAFETY: The transcoder was initialized with a KTX2 file.
+        let source = unsafe { read_source_format(self.raw.as_ptr(), false) };
+        let compiled = crate::compiled_transcode_formats();
+        if !compiled.has_basis_format(source.basis_format) {
+            let code = TranscodeErrorCode::TRANSCODE_ERROR_UNSUPPORTED_BASIS_FORMAT;
+            return Err(TranscodeError {
+                basis_format: source.basis_format,
+                ..error_without_transcoder(code)
+            });
+        }
+        let target_format = crate::select_target_format(&source, params, compiled);
+        let prepare = if animation {
+            crate::ktx2_transcoder_prepare_animation
+        } else {
+            crate::ktx2_transcoder_prepare
+        };
+        // SAFETY: The transcoder was initialized with data that is still alive.
+        let prepared = unsafe {
+            crate::ktx2_transcoder_set_decode_flags(self.raw.as_ptr(), params.decode_flags);
+            prepare(
+                self.raw.as_ptr(),
+                target_format,
+                params.skip_levels,
+                params.max_size,
+            )
+        };
+        if !prepared {
+            return Err(self.error());
+        }
+        let layout = self.read_layout();
+        // SAFETY: The transcoder is prepared.
+        let slices = if animation {
+            0
+        } else {
+            unsafe { crate::ktx2_transcoder_get_slices(self.raw.as_ptr()) }
+        };
+        self.transcoded_slices = (0..slices).map(|_| AtomicBool::new(false)).collect();
+        self.layout = Some(layout);
+        self.animation = animation;
+        #[cfg(all(
+            target_arch = "wasm32",
+            target_vendor = "unknown",
+            target_os = "unknown",
+        ))]
+        {
+            self.params = *params;
+            // The workers are sent the new data with the next job.
+            crate::worker_release_texture(self.worker_texture);
+            self.worker_texture = crate::next_worker_texture_id();
+        }
+        if alloc_dst_buf && let Err(error) = self.alloc_dst_buf() {
+            self.layout = None;
+            return Err(error);
+        }
+        Ok(layout)
+    }
+
+    /// Allocates the destination buffer of a texture prepared with [`Self::prepare_without_dst_buf`], nothing if it's already allocated.
+    pub fn alloc_dst_buf(&mut self) -> Result<(), TranscodeError> {
+        let Some(layout) = self.layout else {
+            return Err(self.error_with(TranscodeErrorCode::TRANSCODE_ERROR_TRANSCODE_LEVEL));
+        };
+        if self.dst_buf_allocated {
+            return Ok(());
+        }
+        // SAFETY: The transcoder is prepared, the buffer is kept alive by `self` until it's prepared again or deleted.
+        if !unsafe { self.dst_buf.alloc(self.raw.as_ptr()) } {
+            return Err(out_of_memory(&layout));
+        }
+        self.dst_buf_allocated = true;
+        Ok(())
+    }
+
+    /// Transcodes all images of a legacy `.basis` file, which are loaded like a KTX2 array or cubemap.
+    pub fn transcode_basis(
+        &mut self,
+        data: &[u8],
+        params: &TranscodeParams,
+    ) -> Result<TranscodedTexture, TranscodeError> {
+        self.layout = None;
+        self.transcoded_slices = Box::new([]);
+        self.dst_buf_allocated = false;
+        self.data = None;
+        // SAFETY: The data is only read during the call.
+        let transcoded = unsafe {
+            crate::ktx2_transcoder_set_decode_flags(self.raw.as_ptr(), params.decode_flags);
+            self.dst_buf
+                .transcode_basis(self.raw.as_ptr(), data, params)
+        };
+        if !transcoded {
+            let error = self.error();
+            // Only the allocation of the destination buffer fails without recording an error.
+            return Err(if error.code == TranscodeErrorCode::TRANSCODE_ERROR_NONE {
+                out_of_memory(&self.read_layout())
+            } else {
+                error
+            });
+        }
+        let layout = self.read_layout();
+        // SAFETY: The whole destination buffer was transcoded.
+        let data = unsafe { self.dst_buf.take(self.raw.as_ptr()) };
+        Ok(TranscodedTexture { layout, data })
+    }
+
+    /// The layout of the prepared texture, if any.
+    pub fn layout(&self) -> Option<TextureLayout> {
+        self.layout
+    }
+
+    /// The number of slices of the prepared texture, 0 for animations.
+    pub fn slices(&self) -> u32 {
+        self.transcoded_slices.len() as u32
+    }
+
+    /// The byte range of a range of slices in the transcoded data.
+    pub fn slice_range(&self, slices: Range<u32>) -> Range<usize> {
+        assert!(slices.start <= slices.end && slices.end <= self.slices());
+        // SAFETY: The slice indices are in bounds, the offset of `slices()` is the end of the buffer.
+        unsafe {
+            crate::ktx2_transcoder_get_slice_offset(self.raw.as_ptr(), slices.start) as usize
+                ..crate::ktx2_transcoder_get_slice_offset(self.raw.as_ptr(), slices.end) as usize
+        }
+    }
+
+    /// Returns the key/value data of the prepared KTX2 file, in file order.
+    pub fn key_values(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        if self.layout.is_none() {
+            return Vec::new();
+        }
+        // SAFETY: The transcoder is prepared, the key/value data is only read.
+        unsafe { crate::ktx2_transcoder_get_key_values(self.raw.as_ptr()) }
+    }
+
+    /// Transcodes a slice of the prepared texture. Each slice can only be transcoded once per [`Self::prepare`].
+    ///
+    /// Slices can be transcoded concurrently from multiple threads, each with its own state.
+    pub fn transcode_slice(
+        &self,
+        slice_index: u32,
+        state: &mut TranscodeState,
+    ) -> Result<(), TranscodeError> {
+        let claimed = self
+            .transcoded_slices
+            .get(slice_index as usize)
+            .is_some_and(|transcoded| !transcoded.swap(true, Ordering::AcqRel));
+        if !claimed || !self.dst_buf_allocated {
+            return Err(self.error_with(TranscodeErrorCode::TRANSCODE_ERROR_TRANSCODE_LEVEL));
+        }
+        // SAFETY: The slice was claimed by this call, so no other thread writes its range of the destination buffer.
+        if unsafe {
+            crate::ktx2_transcoder_transcode_slice(
+                self.raw.as_ptr(),
+                slice_index,
+                state.raw.as_ptr(),
+            )
+        } {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Transcodes a range of slices in the Web Workers started with [`basisu_sys_init_workers`](crate::basisu_sys_init_workers),
+    /// split into at most `max_jobs` contiguous ranges of similar size that are transcoded concurrently.
+    ///
+    /// Returns `Ok(false)` without transcoding if no workers were started.
+    #[cfg(all(
+        target_arch = "wasm32",
+        target_vendor = "unknown",
+        target_os = "unknown",
+    ))]
+    pub async fn transcode_slices_in_workers(
+        &self,
+        slices: Range<u32>,
+        max_jobs: usize,
+    ) -> Result<bool, TranscodeError> {
+        let workers = crate::basisu_sys_worker_count();
+        if workers == 0 {
+            return Ok(false);
+        }
+        let (Some(data), Some(layout), true) = (&self.data, self.layout, self.dst_buf_allocated)
+        else {
+            return Err(self.error_with(TranscodeErrorCode::TRANSCODE_ERROR_TRANSCODE_LEVEL));
+        };
+        for slice_index in slices.clone() {
+            let claimed = self
+                .transcoded_slices
+                .get(slice_index as usize)
+                .is_some_and(|transcoded| !transcoded.swap(true, Ordering::AcqRel));
+            if !claimed {
+                return Err(self.error_with(TranscodeErrorCode::TRANSCODE_ERROR_TRANSCODE_LEVEL));
+            }
+        }
+        if slices.is_empty() {
+            return Ok(true);
+        }
+        // Split at the slices closest to equal byte sizes, the slices of the largest level are much larger than the rest.
+        let offset = |slice| self.slice_range(slice..slice).start;
+        let bytes = self.slice_range(slices.clone());
+        let jobs = max_jobs.min(workers).clamp(1, slices.len());
+        let mut bounds = vec![slices.start];
+        for job in 1..jobs {
+            let split = bytes.start + bytes.len() * job / jobs;
+            let last = *bounds.last().unwrap();
+            if let Some(slice) = (last + 1..slices.end).find(|&slice| offset(slice) >= split) {
+                bounds.push(slice);
+            }
+        }
+        bounds.push(slices.end);
+        // All jobs are started before waiting for the first one.
+        let jobs = bounds
+            .windows(2)
+            .map(|bounds| {
+                let job = crate::worker_transcode_slices(
+                    self.worker_texture,
+                    data,
+                    layout.target_format,
+                    &self.params,
+                    bounds[0]..bounds[1],
+                );
+                (bounds[0], job)
+            })
+            .collect::<Vec<_>>();
+        for (first_slice, job) in jobs {
+            let transcoded = job.result().await?;
+            // SAFETY: The slices were claimed by this call, so nothing else writes their range of the destination buffer.
+            unsafe {
+                crate::ktx2_transcoder_set_r_dst_buf_range(
+                    self.raw.as_ptr(),
+                    offset(first_slice) as u32,
+                    &transcoded,
+                );
+            }
+        }
+        Ok(true)
+    }
+
+    /// Transcodes a frame of the prepared animation into the destination buffer.
+    ///
+    /// The frames of ETC1S video depend on the previous frame, so they have to be transcoded in order with the same state.
+    pub fn transcode_frame(
+        &mut self,
+        frame_index: u32,
+        state: &mut TranscodeState,
+    ) -> Result<(), TranscodeError> {
+        if !self.animation || !self.dst_buf_allocated {
+            return Err(self.error_with(TranscodeErrorCode::TRANSCODE_ERROR_TRANSCODE_LEVEL));
+        }
+        // SAFETY: The transcoder is prepared for animation frames and exclusively borrowed.
+        if unsafe {
+            crate::ktx2_transcoder_transcode_frame(
+                self.raw.as_ptr(),
+                frame_index,
+                state.raw.as_ptr(),
+            )
+        } {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Copies the transcoded data of the prepared texture, e.g. the current frame of an animation.
+    ///
+    /// Slices that weren't transcoded are left zeroed or contain the previous frame.
+    pub fn texture(&mut self) -> Option<TranscodedTexture> {
+        let layout = self.layout?;
+        // SAFETY: The transcoder is prepared.
+        let len = unsafe { crate::ktx2_transcoder_get_r_dst_buf_len(self.raw.as_ptr()) };
+        let data = self.texture_range(0..len as usize)?;
+        Some(TranscodedTexture { layout, data })
+    }
+
+    /// Takes the transcoded data of the prepared texture. On native platforms the data isn't copied.
+    ///
+    /// The transcoder has to be prepared again afterwards.
+    pub fn take_texture(&mut self) -> Option<TranscodedTexture> {
+        if !self.dst_buf_allocated {
+            return None;
+        }
+        let layout = self.layout.take()?;
+        self.transcoded_slices = Box::new([]);
+        self.dst_buf_allocated = false;
+        // SAFETY: The transcoder is prepared and exclusively borrowed, so no slice is being written.
+        let data = unsafe { self.dst_buf.take(self.raw.as_ptr()) };
+        Some(TranscodedTexture { layout, data })
+    }
+
+    /// Copies a byte range of the transcoded data of the prepared texture, see [`Self::slice_range`].
+    ///
+    /// Returns `None` if the transcoder isn't prepared, its destination buffer isn't allocated or the range is out of bounds.
+    pub fn texture_range(&mut self, range: Range<usize>) -> Option<Vec<u8>> {
+        if !self.dst_buf_allocated {
+            return None;
+        }
+        // SAFETY: The transcoder is prepared and exclusively borrowed, so no slice is being written.
+        unsafe { self.dst_buf.copy(self.raw.as_ptr(), range) }
+    }
+
+    fn read_layout(&self) -> TextureLayout {
+        let raw = self.raw.as_ptr();
+        // SAFETY: The transcoder was prepared or transcoded successfully.
+        unsafe {
+            TextureLayout {
+                basis_format: crate::ktx2_transcoder_get_r_basis_format(raw),
+                target_format: crate::ktx2_transcoder_get_r_target_format(raw),
+                is_srgb: crate::ktx2_transcoder_get_r_is_srgb(raw),
+                width: crate::ktx2_transcoder_get_r_width(raw),
+                height: crate::ktx2_transcoder_get_r_height(raw),
+                levels: crate::ktx2_transcoder_get_r_levels(raw),
+                layers: crate::ktx2_transcoder_get_r_layers(raw),
+                faces: crate::ktx2_transcoder_get_r_faces(raw),
+            }
+        }
+    }
+
+    fn error(&self) -> TranscodeError {
+        // SAFETY: The error fields are read behind the mutex they are written with.
+        unsafe { crate::ktx2_transcoder_get_r_error(self.raw.as_ptr()) }
+    }
+
+    /// An error detected before calling into the transcoder.
+    fn error_with(&self, code: TranscodeErrorCode) -> TranscodeError {
+        TranscodeError {
+            code,
+            basis_format: self
+                .layout
+                .map_or(BasisTexFormat(u32::MAX), |layout| layout.basis_format),
+            target_format: self
+                .layout
+                .map_or(TextureTranscodedFormat::cTFTotalTextureFormats, |layout| {
+                    layout.target_format
+                }),
+            ..error_without_transcoder(code)
+        }
+    }
+}
+
+impl Drop for Ktx2Transcoder {
+    fn drop(&mut self) {
+        #[cfg(all(
+            target_arch = "wasm32",
+            target_vendor = "unknown",
+            target_os = "unknown",
+        ))]
+        crate::worker_release_texture(self.worker_texture);
+        // SAFETY: The transcoder is no longer used, its data is dropped afterwards.
+        unsafe { crate::ktx2_transcoder_delete(self.raw.as_ptr()) };
+    }
+}
+
+/// Reads what an initialized transcoder reports to select the target format.
+///
+/// # Safety
+///
+/// The transcoder must be initialized with a `.basis` file if `basis` is true, or a KTX2 file otherwise.
+pub(crate) unsafe fn read_source_format(transcoder: *mut Transcoder, basis: bool) -> SourceFormat {
+    // SAFETY: The transcoder is initialized, the getters only read it.
+    unsafe {
+        SourceFormat {
+            basis_format: crate::ktx2_transcoder_get_r_basis_format(transcoder),
+            is_srgb: crate::ktx2_transcoder_get_r_is_srgb(transcoder),
+            channels: if basis {
+                SourceChannels::Basis {
+                    has_alpha_slices: crate::ktx2_transcoder_get_r_has_alpha_slices(transcoder),
+                }
+            } else {
+                SourceChannels::Dfd {
+                    channel_id0: crate::ktx2_transcoder_get_r_dfd_channel_id0(transcoder),
+                    channel_id1: crate::ktx2_transcoder_get_r_dfd_channel_id1(transcoder),
+                }
+            },
+        }
+    }
+}
+
+fn out_of_memory(layout: &TextureLayout) -> TranscodeError {
+    TranscodeError {
+        basis_format: layout.basis_format,
+        target_format: layout.target_format,
+        width: layout.width,
+        height: layout.height,
+        ..error_without_transcoder(TranscodeErrorCode::TRANSCODE_ERROR_OUT_OF_MEMORY)
+    }
+}
+
+pub(crate) fn error_without_transcoder(code: TranscodeErrorCode) -> TranscodeError {
+    TranscodeError {
+        code,
+        basis_format: BasisTexFormat(u32::MAX),
+        target_format: TextureTranscodedFormat::cTFTotalTextureFormats,
+        level: 0,
+        layer: 0,
+        face: 0,
+        width: 0,
+        height: 0,
+    }
+}
+
+/// The state carried between the slices or frames transcoded by a thread, e.g. the previous frame of ETC1S video.
+pub struct TranscodeState {
+    raw: NonNull<TranscoderState>,
+}
+
+// SAFETY: The state is only used through `&mut self`.
+unsafe impl Send for TranscodeState {}
+// SAFETY: `TranscodeState` has no methods taking `&self`.
+unsafe impl Sync for TranscodeState {}
+
+impl TranscodeState {
+    pub fn new() -> Result<Self, TranscodeError> {
+        // SAFETY: Creating a state has no preconditions.
+        let raw = NonNull::new(unsafe { crate::ktx2_transcoder_state_new() }).ok_or_else(|| {
+            error_without_transcoder(TranscodeErrorCode::TRANSCODE_ERROR_OUT_OF_MEMORY)
+        })?;
+        Ok(Self { raw })
+    }
+}
+
+impl Drop for TranscodeState {
+    fn drop(&mut self) {
+        // SAFETY: The state is no longer used.
+        unsafe { crate::ktx2_transcoder_state_delete(self.raw.as_ptr()) };
+    }
+}
+
+impl fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.code {
+            TranscodeErrorCode::TRANSCODE_ERROR_NONE => "no error",
+            TranscodeErrorCode::TRANSCODE_ERROR_INVALID_HEADER => "invalid header",
+            TranscodeErrorCode::TRANSCODE_ERROR_UNSUPPORTED_BASIS_FORMAT => {
+                "unsupported basis format"
+            }
+            TranscodeErrorCode::TRANSCODE_ERROR_UNSUPPORTED_TARGET => "unsupported target format",
+            TranscodeErrorCode::TRANSCODE_ERROR_INCOMPATIBLE_IMAGES => "incompatible images",
+            TranscodeErrorCode::TRANSCODE_ERROR_TRANSCODE_LEVEL => "failed to transcode level",
+            TranscodeErrorCode::TRANSCODE_ERROR_ZSTD => "failed to decompress zstd level",
+            TranscodeErrorCode::TRANSCODE_ERROR_OUT_OF_MEMORY => "out of memory",
+            _ => "unknown error",
+        };
+        write!(
+            f,
+            "{reason} (level {}, layer {}, face {}, {}x{})",
+            self.level, self.layer, self.face, self.width, self.height
+        )
+    }
+}
+
+impl std::error::Error for TranscodeError {}
@@ -1,23 +1,34 @@
 #![expect(clippy::missing_safety_doc, reason = "TODO")]
 
-use std::cell::OnceCell;
+use core::ops::Range;
+use std::cell::{Cell, OnceCell, RefCell};
 
 use js_sys::Object;
 use js_sys::Reflect;
 use js_sys::Uint8Array;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
 
-use crate::ChannelType;
-use crate::TextureCompressionMethod;
+use crate::BasisTexFormat;
+use crate::CompiledTranscodeFormats;
+use crate::DecodeFlags;
 use crate::TextureTranscodedFormat;
+use crate::TranscodeError;
+use crate::TranscodeErrorCode;
+use crate::TranscodeParams;
 use crate::Transcoder;
+use crate::TranscoderState;
 
 mod bindings_sys {
     use super::Transcoder;
+    use super::TranscoderState;
     use js_sys::Uint8Array;
     use wasm_bindgen::prelude::wasm_bindgen;
-    type TextureCompressionMethodRepr = u8;
     type TextureTranscodedFormatRepr = u32;
-    type ChannelTypeRepr = u8;
+    type BasisTexFormatRepr = u32;
+    type TranscodeErrorCodeRepr = u32;
+    type DecodeFlagsRepr = u32;
+    type CompiledTranscodeFormatsRepr = u32;
 
     #[wasm_bindgen]
     extern "C" {
@@ -33,6 +44,8 @@ mod bindings_sys {
 
         #[wasm_bindgen(method,js_name=_c_basisu_transcoder_init)]
         pub fn js_basisu_transcoder_init(this: &BasisuVendor);
+        #[wasm_bindgen(method,js_name=_c_basisu_get_compiled_formats)]
+        pub fn js_basisu_get_compiled_formats(this: &BasisuVendor) -> CompiledTranscodeFormatsRepr;
         #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_new)]
         pub fn js_ktx2_transcoder_new(this: &BasisuVendor) -> *mut Transcoder;
         #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_delete)]
@@ -43,10 +56,112 @@ mod bindings_sys {
             transcoder: *mut Transcoder,
             data: usize,
             data_len: u32,
-            supported_compressed_formats: TextureCompressionMethodRepr,
-            channel_type_hint: ChannelTypeRepr,
-            force_transcode_target: TextureTranscodedFormatRepr,
+            target_format: TextureTranscodedFormatRepr,
         ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_basis_transcoder_init)]
+        pub fn js_basis_transcoder_init(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            data: usize,
+            data_len: u32,
+        ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_basis_transcoder_transcode_image)]
+        pub fn js_basis_transcoder_transcode_image(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            data: usize,
+            data_len: u32,
+            target_format: TextureTranscodedFormatRepr,
+            skip_levels: u32,
+            max_size: u32,
+        ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_alloc_src_buf)]
+        pub fn js_ktx2_transcoder_alloc_src_buf(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            size: u32,
+        ) -> u32;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_init)]
+        pub fn js_ktx2_transcoder_init(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            data: u32,
+            data_len: u32,
+        ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_prepare)]
+        pub fn js_ktx2_transcoder_prepare(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            target_format: TextureTranscodedFormatRepr,
+            skip_levels: u32,
+            max_size: u32,
+        ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_prepare_animation)]
+        pub fn js_ktx2_transcoder_prepare_animation(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            target_format: TextureTranscodedFormatRepr,
+            skip_levels: u32,
+            max_size: u32,
+        ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_transcode_frame)]
+        pub fn js_ktx2_transcoder_transcode_frame(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            frame_index: u32,
+            state: *mut TranscoderState,
+        ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_slices)]
+        pub fn js_ktx2_transcoder_get_slices(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> ::std::os::raw::c_uint;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_slice_offset)]
+        pub fn js_ktx2_transcoder_get_slice_offset(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            slice_index: u32,
+        ) -> ::std::os::raw::c_uint;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_state_new)]
+        pub fn js_ktx2_transcoder_state_new(this: &BasisuVendor) -> *mut TranscoderState;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_state_delete)]
+        pub fn js_ktx2_transcoder_state_delete(this: &BasisuVendor, state: *mut TranscoderState);
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_transcode_slice)]
+        pub fn js_ktx2_transcoder_transcode_slice(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            slice_index: u32,
+            state: *mut TranscoderState,
+        ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_key_values)]
+        pub fn js_ktx2_transcoder_get_key_values(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> u32;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_key)]
+        pub fn js_ktx2_transcoder_get_key(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            index: u32,
+        ) -> u32;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_key_len)]
+        pub fn js_ktx2_transcoder_get_key_len(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            index: u32,
+        ) -> u32;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_value)]
+        pub fn js_ktx2_transcoder_get_value(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            index: u32,
+        ) -> u32;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_value_len)]
+        pub fn js_ktx2_transcoder_get_value_len(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            index: u32,
+        ) -> u32;
         #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_dst_buf)]
         pub fn js_ktx2_transcoder_get_r_dst_buf(
             this: &BasisuVendor,
@@ -92,6 +207,62 @@ mod bindings_sys {
             this: &BasisuVendor,
             transcoder: *mut Transcoder,
         ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_dfd_channel_id0)]
+        pub fn js_ktx2_transcoder_get_r_dfd_channel_id0(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> u32;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_dfd_channel_id1)]
+        pub fn js_ktx2_transcoder_get_r_dfd_channel_id1(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> u32;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_has_alpha_slices)]
+        pub fn js_ktx2_transcoder_get_r_has_alpha_slices(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> bool;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_basis_format)]
+        pub fn js_ktx2_transcoder_get_r_basis_format(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> BasisTexFormatRepr;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_error)]
+        pub fn js_ktx2_transcoder_get_r_error(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> TranscodeErrorCodeRepr;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_error_level)]
+        pub fn js_ktx2_transcoder_get_r_error_level(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> ::std::os::raw::c_uint;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_error_layer)]
+        pub fn js_ktx2_transcoder_get_r_error_layer(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> ::std::os::raw::c_uint;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_error_face)]
+        pub fn js_ktx2_transcoder_get_r_error_face(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> ::std::os::raw::c_uint;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_error_width)]
+        pub fn js_ktx2_transcoder_get_r_error_width(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> ::std::os::raw::c_uint;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_get_r_error_height)]
+        pub fn js_ktx2_transcoder_get_r_error_height(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+        ) -> ::std::os::raw::c_uint;
+        #[wasm_bindgen(method,js_name=_c_ktx2_transcoder_set_decode_flags)]
+        pub fn js_ktx2_transcoder_set_decode_flags(
+            this: &BasisuVendor,
+            transcoder: *mut Transcoder,
+            decode_flags: DecodeFlagsRepr,
+        );
     }
 }
 
@@ -107,10 +278,44 @@ mod bindings_vendor {
     }
 }
 
+mod bindings_worker_pool {
+    use js_sys::{Object, Promise, Uint8Array, Uint32Array};
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen(module = "/src/worker_pool.js")]
+    extern "C" {
+        #[derive(Debug)]
+        pub type BasisuWorker;
+        #[wasm_bindgen(catch, js_name = "createWorker")]
+        pub async fn create_worker(
+            vendor_source: &str,
+            wasm_binary: &Uint8Array,
+        ) -> Result<BasisuWorker, JsValue>;
+        #[wasm_bindgen(js_name = "transcodeSlices")]
+        pub fn transcode_slices(worker: &BasisuWorker, job: &Object) -> Promise;
+        #[wasm_bindgen(js_name = "releaseTexture")]
+        pub fn release_texture(worker: &BasisuWorker, texture_id: f64);
+
+        pub type WorkerTranscodeResult;
+        #[wasm_bindgen(method, getter)]
+        pub fn data(this: &WorkerTranscodeResult) -> Option<Uint8Array>;
+        #[wasm_bindgen(method, getter)]
+        pub fn error(this: &WorkerTranscodeResult) -> Option<Uint32Array>;
+    }
+}
+
 const BASISU_VENDOR_WASM: &[u8] = include_bytes!("../wasm/basisu_vendor.wasm");
+// The workers import the vendor module from a blob URL, so that it doesn't depend on where the bundler puts it.
+const BASISU_VENDOR_JS: &str = include_str!("../wasm/basisu_vendor.js");
 
 thread_local! {
     static BASISU_VENDOR_INSTANCE: OnceCell<bindings_sys::BasisuVendor> = const{ OnceCell::new() };
+    static BASISU_WORKERS: RefCell<Vec<bindings_worker_pool::BasisuWorker>> = const { RefCell::new(Vec::new()) };
+    static NEXT_WORKER: Cell<usize> = const { Cell::new(0) };
+    /// The texture each worker keeps the KTX2 data of, 0 for none. Messages to a worker are handled in order, so it matches what the worker keeps when the next job arrives.
+    static WORKER_TEXTURES: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+    static NEXT_WORKER_TEXTURE: Cell<u64> = const { Cell::new(1) };
 }
 
 pub async fn basisu_sys_init_vendor() {
@@ -123,18 +328,153 @@ pub async fn basisu_sys_init_vendor() {
     });
 }
 
+/// Starts `count` Web Workers that transcode KTX2 slices off the main thread, each with its own instance of the vendor wasm.
+///
+/// Workers that started are kept if a later one fails, e.g. because the content security policy forbids blob workers.
+pub async fn basisu_sys_init_workers(count: usize) -> Result<(), JsValue> {
+    let binary = Uint8Array::new_from_slice(BASISU_VENDOR_WASM);
+    for _ in 0..count {
+        let worker = bindings_worker_pool::create_worker(BASISU_VENDOR_JS, &binary).await?;
+        BASISU_WORKERS.with_borrow_mut(|workers| workers.push(worker));
+        WORKER_TEXTURES.with_borrow_mut(|textures| textures.push(0));
+    }
+    Ok(())
+}
+
+/// The number of Web Workers started with [`basisu_sys_init_workers`].
+pub fn basisu_sys_worker_count() -> usize {
+    BASISU_WORKERS.with_borrow(Vec::len)
+}
+
+/// A range of slices being transcoded in a Web Worker, see [`worker_transcode_slices`].
+pub(crate) struct WorkerJob(js_sys::Promise);
+
+impl WorkerJob {
+    /// Waits for the transcoded data of the slices.
+    pub(crate) async fn result(self) -> Result<Uint8Array, TranscodeError> {
+        let result = match JsFuture::from(self.0).await {
+            Ok(result) => result.unchecked_into::<bindings_worker_pool::WorkerTranscodeResult>(),
+            // The worker failed with an uncaught error.
+            Err(_) => {
+                return Err(crate::transcoder::error_without_transcoder(
+                    TranscodeErrorCode::TRANSCODE_ERROR_TRANSCODE_LEVEL,
+                ));
+            }
+        };
+        if let Some(data) = result.data() {
+            return Ok(data);
+        }
+        let error = result.error().unwrap().to_vec();
+        Err(TranscodeError {
+            code: TranscodeErrorCode(error[0]),
+            basis_format: BasisTexFormat(error[1]),
+            target_format: TextureTranscodedFormat(error[2]),
+            level: error[3],
+            layer: error[4],
+            face: error[5],
+            width: error[6],
+            height: error[7],
+        })
+    }
+}
+
+/// Returns a new id for the data of a texture transcoded by the Web Workers, see [`worker_transcode_slices`].
+pub(crate) fn next_worker_texture_id() -> u64 {
+    NEXT_WORKER_TEXTURE.with(|next| next.replace(next.get() + 1))
+}
+
+/// Starts transcoding a range of slices in the next Web Worker, which prepares its own transcoder with the data, target format and parameters.
+///
+/// Each worker keeps the data of the last texture it was sent, so `data` is only copied and transferred to a worker
+/// for the first job of `texture_id`. The worker only allocates the transcoded size of the slices.
+///
+/// # Panics
+///
+/// If no workers were started with [`basisu_sys_init_workers`].
+pub(crate) fn worker_transcode_slices(
+    texture_id: u64,
+    data: &[u8],
+    target_format: TextureTranscodedFormat,
+    params: &TranscodeParams,
+    slices: Range<u32>,
+) -> WorkerJob {
+    let job = Object::new();
+    let set = |key: &str, value: JsValue| {
+        Reflect::set(&job, &key.into(), &value).unwrap();
+    };
+    let index = NEXT_WORKER.with(|next| next.replace(next.get().wrapping_add(1)));
+    let index = index % basisu_sys_worker_count();
+    let sent = WORKER_TEXTURES.with_borrow_mut(|textures| {
+        core::mem::replace(&mut textures[index], texture_id) == texture_id
+    });
+    if !sent {
+        set("data", Uint8Array::from(data).into());
+    }
+    // Ids stay below 2^53, so they're exact as JS numbers.
+    set("textureId", (texture_id as f64).into());
+    set("targetFormat", target_format.0.into());
+    set("skipLevels", params.skip_levels.into());
+    set("maxSize", params.max_size.into());
+    set("decodeFlags", params.decode_flags.0.into());
+    set("firstSlice", slices.start.into());
+    set("endSlice", slices.end.into());
+    BASISU_WORKERS.with_borrow(|workers| {
+        WorkerJob(bindings_worker_pool::transcode_slices(
+            &workers[index],
+            &job,
+        ))
+    })
+}
+
+/// Frees the data of a texture kept by the Web Workers, once it isn't transcoded anymore.
+pub(crate) fn worker_release_texture(texture_id: u64) {
+    if texture_id == 0 {
+        return;
+    }
+    WORKER_TEXTURES.with_borrow_mut(|textures| {
+        BASISU_WORKERS.with_borrow(|workers| {
+            for (worker, texture) in workers.iter().zip(textures) {
+                if *texture == texture_id {
+                    bindings_worker_pool::release_texture(worker, texture_id as f64);
+                    *texture = 0;
+                }
+            }
+        });
+    });
+}
+
 pub unsafe fn basisu_transcoder_init() {
     BASISU_VENDOR_INSTANCE.with(|inst| {
         let inst = inst.get().unwrap();
         inst.js_basisu_transcoder_init()
     })
 }
+/// The formats the embedded `basisu_vendor.wasm` was built with, see `build-wasm-cli`.
+///
+/// # Panics
+///
+/// If the vendor wasn't initialized with [`basisu_sys_init_vendor`].
+pub fn compiled_transcode_formats() -> CompiledTranscodeFormats {
+    CompiledTranscodeFormats(BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_basisu_get_compiled_formats()
+    }))
+}
 pub unsafe fn ktx2_transcoder_delete(transcoder: *mut Transcoder) {
     BASISU_VENDOR_INSTANCE.with(|inst| {
         let inst = inst.get().unwrap();
         inst.js_ktx2_transcoder_delete(transcoder)
     })
 }
+pub unsafe fn ktx2_transcoder_set_decode_flags(
+    transcoder: *mut Transcoder,
+    decode_flags: DecodeFlags,
+) {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_set_decode_flags(transcoder, decode_flags.0)
+    })
+}
 pub unsafe fn ktx2_transcoder_get_r_faces(transcoder: *mut Transcoder) -> u32 {
     BASISU_VENDOR_INSTANCE.with(|inst| {
         let inst = inst.get().unwrap();
@@ -153,6 +493,24 @@ pub unsafe fn ktx2_transcoder_get_r_is_srgb(transcoder: *mut Transcoder) -> bool
         inst.js_ktx2_transcoder_get_r_is_srgb(transcoder)
     })
 }
+pub unsafe fn ktx2_transcoder_get_r_dfd_channel_id0(transcoder: *mut Transcoder) -> u32 {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_get_r_dfd_channel_id0(transcoder)
+    })
+}
+pub unsafe fn ktx2_transcoder_get_r_dfd_channel_id1(transcoder: *mut Transcoder) -> u32 {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_get_r_dfd_channel_id1(transcoder)
+    })
+}
+pub unsafe fn ktx2_transcoder_get_r_has_alpha_slices(transcoder: *mut Transcoder) -> bool {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_get_r_has_alpha_slices(transcoder)
+    })
+}
 pub unsafe fn ktx2_transcoder_get_r_layers(transcoder: *mut Transcoder) -> u32 {
     BASISU_VENDOR_INSTANCE.with(|inst| {
         let inst = inst.get().unwrap();
@@ -173,6 +531,30 @@ pub unsafe fn ktx2_transcoder_get_r_target_format(
         inst.js_ktx2_transcoder_get_r_target_format(transcoder)
     }))
 }
+pub unsafe fn ktx2_transcoder_get_r_basis_format(transcoder: *mut Transcoder) -> BasisTexFormat {
+    BasisTexFormat(BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_get_r_basis_format(transcoder)
+    }))
+}
+/// Returns why and where the last transcoder function returned false.
+pub unsafe fn ktx2_transcoder_get_r_error(transcoder: *mut Transcoder) -> TranscodeError {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        TranscodeError {
+            code: TranscodeErrorCode(inst.js_ktx2_transcoder_get_r_error(transcoder)),
+            basis_format: BasisTexFormat(inst.js_ktx2_transcoder_get_r_basis_format(transcoder)),
+            target_format: TextureTranscodedFormat(
+                inst.js_ktx2_transcoder_get_r_target_format(transcoder),
+            ),
+            level: inst.js_ktx2_transcoder_get_r_error_level(transcoder),
+            layer: inst.js_ktx2_transcoder_get_r_error_layer(transcoder),
+            face: inst.js_ktx2_transcoder_get_r_error_face(transcoder),
+            width: inst.js_ktx2_transcoder_get_r_error_width(transcoder),
+            height: inst.js_ktx2_transcoder_get_r_error_height(transcoder),
+        }
+    })
+}
 pub unsafe fn ktx2_transcoder_get_r_width(transcoder: *mut Transcoder) -> u32 {
     BASISU_VENDOR_INSTANCE.with(|inst| {
         let inst = inst.get().unwrap();
@@ -189,9 +571,7 @@ pub unsafe fn ktx2_transcoder_new() -> *mut Transcoder {
 pub unsafe fn ktx2_transcoder_transcode_image(
     transcoder: *mut Transcoder,
     data: Vec<u8>,
-    supported_compressed_formats: TextureCompressionMethod,
-    channel_type_hint: ChannelType,
-    force_transcode_target: TextureTranscodedFormat,
+    target_format: TextureTranscodedFormat,
 ) -> bool {
     BASISU_VENDOR_INSTANCE.with(|inst| {
         let inst = inst.get().unwrap();
@@ -199,19 +579,155 @@ pub unsafe fn ktx2_transcoder_transcode_image(
         let ptr = inst.js_basisu_malloc(len as usize);
         let heap = inst.js_basisu_heapu8();
         heap.set(&data.into(), ptr as u32);
-        let result = inst.js_ktx2_transcoder_transcode_image(
+        let result = inst.js_ktx2_transcoder_transcode_image(transcoder, ptr, len, target_format.0);
+        inst.js_basisu_free(ptr);
+        result
+    })
+}
+
+/// Parses the KTX2 header and data format descriptor, so that the target format can be selected before [`ktx2_transcoder_prepare`].
+///
+/// The data is copied into a buffer owned by the transcoder.
+pub unsafe fn ktx2_transcoder_init(transcoder: *mut Transcoder, data: &[u8]) -> bool {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        let len = u32::try_from(data.len()).unwrap();
+        let ptr = inst.js_ktx2_transcoder_alloc_src_buf(transcoder, len);
+        let heap = inst.js_basisu_heapu8();
+        heap.set(&Uint8Array::from(data), ptr);
+        inst.js_ktx2_transcoder_init(transcoder, ptr, len)
+    })
+}
+
+/// Prepares the initialized transcoder for [`ktx2_transcoder_transcode_slice`].
+///
+/// The first `skip_levels` levels and the levels larger than `max_size` (if not 0) are skipped.
+pub unsafe fn ktx2_transcoder_prepare(
+    transcoder: *mut Transcoder,
+    target_format: TextureTranscodedFormat,
+    skip_levels: u32,
+    max_size: u32,
+) -> bool {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_prepare(transcoder, target_format.0, skip_levels, max_size)
+    })
+}
+
+/// Prepares the initialized transcoder for [`ktx2_transcoder_transcode_frame`], the destination buffer only holds a single frame.
+///
+/// The first `skip_levels` levels and the levels larger than `max_size` (if not 0) are skipped.
+pub unsafe fn ktx2_transcoder_prepare_animation(
+    transcoder: *mut Transcoder,
+    target_format: TextureTranscodedFormat,
+    skip_levels: u32,
+    max_size: u32,
+) -> bool {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_prepare_animation(
+            transcoder,
+            target_format.0,
+            skip_levels,
+            max_size,
+        )
+    })
+}
+pub unsafe fn ktx2_transcoder_get_slices(transcoder: *mut Transcoder) -> u32 {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_get_slices(transcoder)
+    })
+}
+pub unsafe fn ktx2_transcoder_get_slice_offset(
+    transcoder: *mut Transcoder,
+    slice_index: u32,
+) -> u32 {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_get_slice_offset(transcoder, slice_index)
+    })
+}
+pub unsafe fn ktx2_transcoder_state_new() -> *mut TranscoderState {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_state_new()
+    })
+}
+pub unsafe fn ktx2_transcoder_state_delete(state: *mut TranscoderState) {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_state_delete(state)
+    })
+}
+pub unsafe fn ktx2_transcoder_transcode_frame(
+    transcoder: *mut Transcoder,
+    frame_index: u32,
+    state: *mut TranscoderState,
+) -> bool {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_transcode_frame(transcoder, frame_index, state)
+    })
+}
+pub unsafe fn ktx2_transcoder_transcode_slice(
+    transcoder: *mut Transcoder,
+    slice_index: u32,
+    state: *mut TranscoderState,
+) -> bool {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_transcode_slice(transcoder, slice_index, state)
+    })
+}
+
+pub unsafe fn basis_transcoder_transcode_image(
+    transcoder: *mut Transcoder,
+    data: &[u8],
+    target_format: TextureTranscodedFormat,
+    skip_levels: u32,
+    max_size: u32,
+) -> bool {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        let len = u32::try_from(data.len()).unwrap();
+        let ptr = inst.js_basisu_malloc(len as usize);
+        let heap = inst.js_basisu_heapu8();
+        heap.set(&Uint8Array::from(data), ptr as u32);
+        let result = inst.js_basis_transcoder_transcode_image(
             transcoder,
             ptr,
             len,
-            supported_compressed_formats.0,
-            channel_type_hint.0,
-            force_transcode_target.0,
+            target_format.0,
+            skip_levels,
+            max_size,
         );
         inst.js_basisu_free(ptr);
         result
     })
 }
 
+/// Parses the header of a `.basis` file, so that the target format can be selected before [`basis_transcoder_transcode_image`].
+pub unsafe fn basis_transcoder_init(transcoder: *mut Transcoder, data: &[u8]) -> bool {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        let len = u32::try_from(data.len()).unwrap();
+        let ptr = inst.js_basisu_malloc(len as usize);
+        let heap = inst.js_basisu_heapu8();
+        heap.set(&Uint8Array::from(data), ptr as u32);
+        let result = inst.js_basis_transcoder_init(transcoder, ptr, len);
+        inst.js_basisu_free(ptr);
+        result
+    })
+}
+
+pub unsafe fn ktx2_transcoder_get_r_dst_buf_len(transcoder: *mut Transcoder) -> u32 {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        inst.js_ktx2_transcoder_get_r_dst_buf_len(transcoder)
+    })
+}
+
 pub unsafe fn ktx2_transcoder_get_r_dst_buf(transcoder: *mut Transcoder) -> Vec<u8> {
     BASISU_VENDOR_INSTANCE.with(|inst| {
         let inst = inst.get().unwrap();
@@ -222,3 +738,119 @@ pub unsafe fn ktx2_transcoder_get_r_dst_buf(transcoder: *mut Transcoder) -> Vec<
             .to_vec()
     })
 }
+
+/// Copies a byte range of the destination buffer, `None` if it's out of bounds.
+pub unsafe fn ktx2_transcoder_get_r_dst_buf_range(
+    transcoder: *mut Transcoder,
+    range: core::ops::Range<u32>,
+) -> Option<Vec<u8>> {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        let dst_buf = inst.js_ktx2_transcoder_get_r_dst_buf(transcoder);
+        let dst_len = inst.js_ktx2_transcoder_get_r_dst_buf_len(transcoder);
+        if range.start > range.end || range.end > dst_len {
+            return None;
+        }
+        Some(
+            inst.js_basisu_heapu8()
+                .subarray(dst_buf + range.start, dst_buf + range.end)
+                .to_vec(),
+        )
+    })
+}
+
+/// Writes data transcoded by a Web Worker into the destination buffer at `offset`.
+pub(crate) unsafe fn ktx2_transcoder_set_r_dst_buf_range(
+    transcoder: *mut Transcoder,
+    offset: u32,
+    data: &Uint8Array,
+) {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        let dst_buf = inst.js_ktx2_transcoder_get_r_dst_buf(transcoder);
+        let dst_len = inst.js_ktx2_transcoder_get_r_dst_buf_len(transcoder);
+        assert!(
+            offset
+                .checked_add(data.length())
+                .is_some_and(|end| end <= dst_len)
+        );
+        inst.js_basisu_heapu8().set(data, dst_buf + offset);
+    })
+}
+
+/// Returns the key/value data of a prepared KTX2 file, in file order.
+pub unsafe fn ktx2_transcoder_get_key_values(
+    transcoder: *mut Transcoder,
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+    BASISU_VENDOR_INSTANCE.with(|inst| {
+        let inst = inst.get().unwrap();
+        let heap = inst.js_basisu_heapu8();
+        let count = inst.js_ktx2_transcoder_get_key_values(transcoder);
+        (0..count)
+            .map(|index| {
+                let key = inst.js_ktx2_transcoder_get_key(transcoder, index);
+                let key_len = inst.js_ktx2_transcoder_get_key_len(transcoder, index);
+                let value = inst.js_ktx2_transcoder_get_value(transcoder, index);
+                let value_len = inst.js_ktx2_transcoder_get_value_len(transcoder, index);
+                (
+                    heap.subarray(key, key + key_len).to_vec(),
+                    heap.subarray(value, value + value_len).to_vec(),
+                )
+            })
+            .collect()
+    })
+}
+
+/// The destination buffer of a [`Ktx2Transcoder`](crate::Ktx2Transcoder).
+///
+/// It lives in the memory of the wasm instance, so the transcoded data is always copied out.
+#[derive(Default)]
+pub(crate) struct DstBuf;
+
+impl DstBuf {
+    pub(crate) unsafe fn new(_transcoder: *mut Transcoder) -> Self {
+        Self
+    }
+
+    /// The destination buffer is allocated by prepare.
+    pub(crate) unsafe fn alloc(&mut self, _transcoder: *mut Transcoder) -> bool {
+        true
+    }
+
+    /// Selects the target format of a `.basis` file, prepares it, allocates the destination buffer and transcodes into it.
+    pub(crate) unsafe fn transcode_basis(
+        &mut self,
+        transcoder: *mut Transcoder,
+        data: &[u8],
+        params: &TranscodeParams,
+    ) -> bool {
+        unsafe {
+            if !basis_transcoder_init(transcoder, data) {
+                return false;
+            }
+            let source = crate::read_source_format(transcoder, true);
+            basis_transcoder_transcode_image(
+                transcoder,
+                data,
+                crate::select_target_format(&source, params, crate::compiled_transcode_formats()),
+                params.skip_levels,
+                params.max_size,
+            )
+        }
+    }
+
+    /// Copies a byte range of the buffer, `None` if it's out of bounds.
+    pub(crate) unsafe fn copy(
+        &self,
+        transcoder: *mut Transcoder,
+        range: Range<usize>,
+    ) -> Option<Vec<u8>> {
+        let range = u32::try_from(range.start).ok()?..u32::try_from(range.end).ok()?;
+        unsafe { ktx2_transcoder_get_r_dst_buf_range(transcoder, range) }
+    }
+
+    /// Copies the whole buffer.
+    pub(crate) unsafe fn take(&mut self, transcoder: *mut Transcoder) -> Vec<u8> {
+        unsafe { ktx2_transcoder_get_r_dst_buf(transcoder) }
+    }
+}
@@ -0,0 +1,162 @@
+// Web Workers that transcode KTX2 slices off the main thread, each with its own `basisu_vendor` instance.
+//
+// A job prepares a transcoder in the worker with the target format and parameters the main thread selected,
+// transcodes a range of slices into a buffer of just their size and transfers it back. Each worker keeps the KTX2 data
+// of the last texture it was sent, so it's only sent with the first job of a texture until the texture is released.
+
+const WORKER_SOURCE = `
+let vendor;
+// The texture of the last job with data: { id, transcoder, error }, where \`error\` is set if the data couldn't be initialized.
+let texture;
+
+const TRANSCODE_ERROR_TRANSCODE_LEVEL = 5;
+
+self.onmessage = async (event) => {
+	const message = event.data;
+	if (message.vendorUrl) {
+		try {
+			const module = await import(message.vendorUrl);
+			vendor = await module.default({ wasmBinary: message.wasmBinary });
+			vendor._c_basisu_transcoder_init();
+			self.postMessage({ ready: true });
+		} catch (error) {
+			self.postMessage({ initError: String(error) });
+		}
+		return;
+	}
+	if (message.releaseTextureId !== undefined) {
+		if (texture && texture.id === message.releaseTextureId) {
+			releaseTexture();
+		}
+		return;
+	}
+	const result = transcode(message);
+	self.postMessage(result, result.data ? [result.data.buffer] : []);
+};
+
+function releaseTexture() {
+	vendor._c_ktx2_transcoder_delete(texture.transcoder);
+	texture = undefined;
+}
+
+function readError(transcoder) {
+	return new Uint32Array([
+		vendor._c_ktx2_transcoder_get_r_error(transcoder),
+		vendor._c_ktx2_transcoder_get_r_basis_format(transcoder),
+		vendor._c_ktx2_transcoder_get_r_target_format(transcoder),
+		vendor._c_ktx2_transcoder_get_r_error_level(transcoder),
+		vendor._c_ktx2_transcoder_get_r_error_layer(transcoder),
+		vendor._c_ktx2_transcoder_get_r_error_face(transcoder),
+		vendor._c_ktx2_transcoder_get_r_error_width(transcoder),
+		vendor._c_ktx2_transcoder_get_r_error_height(transcoder),
+	]);
+}
+
+// Replaces the kept texture with the data of a job. The transcoder owns a copy of the data in the wasm heap.
+function loadTexture(id, data) {
+	if (texture) {
+		releaseTexture();
+	}
+	const transcoder = vendor._c_ktx2_transcoder_new();
+	const bytes = new Uint8Array(data);
+	const src = vendor._c_ktx2_transcoder_alloc_src_buf(transcoder, bytes.length);
+	vendor.HEAPU8.set(bytes, src);
+	const ok = vendor._c_ktx2_transcoder_init(transcoder, src, bytes.length);
+	texture = { id, transcoder, error: ok ? undefined : readError(transcoder) };
+}
+
+function transcode(job) {
+	if (job.data) {
+		loadTexture(job.textureId, job.data);
+	}
+	if (!texture || texture.id !== job.textureId) {
+		const error = new Uint32Array(8);
+		error[0] = TRANSCODE_ERROR_TRANSCODE_LEVEL;
+		return { id: job.id, error };
+	}
+	if (texture.error) {
+		return { id: job.id, error: texture.error };
+	}
+	const transcoder = texture.transcoder;
+	const state = vendor._c_ktx2_transcoder_state_new();
+	vendor._c_ktx2_transcoder_set_decode_flags(transcoder, job.decodeFlags);
+	let ok = vendor._c_ktx2_transcoder_prepare_slices(transcoder, job.targetFormat, job.skipLevels, job.maxSize, job.firstSlice, job.endSlice);
+	for (let slice = job.firstSlice; ok && slice < job.endSlice; slice++) {
+		ok = vendor._c_ktx2_transcoder_transcode_slice(transcoder, slice, state);
+	}
+	let result;
+	if (ok) {
+		// The destination buffer only holds the slices of the job.
+		const dst = vendor._c_ktx2_transcoder_get_r_dst_buf(transcoder);
+		const len = vendor._c_ktx2_transcoder_get_r_dst_buf_len(transcoder);
+		result = { id: job.id, data: vendor.HEAPU8.slice(dst, dst + len) };
+	} else {
+		result = { id: job.id, error: readError(transcoder) };
+	}
+	vendor._c_ktx2_transcoder_state_delete(state);
+	return result;
+}
+`;
+
+let nextJobId = 0;
+
+// Starts a worker and resolves once its `basisu_vendor` instance is initialized.
+export async function createWorker(vendorSource, wasmBinary) {
+	const vendorUrl = URL.createObjectURL(new Blob([vendorSource], { type: "text/javascript" }));
+	const workerUrl = URL.createObjectURL(new Blob([WORKER_SOURCE], { type: "text/javascript" }));
+	const worker = new Worker(workerUrl, { type: "module" });
+	try {
+		await new Promise((resolve, reject) => {
+			worker.onmessage = (event) => {
+				if (event.data.ready) {
+					resolve();
+				} else {
+					reject(new Error(event.data.initError));
+				}
+			};
+			worker.onerror = (event) => reject(new Error(event.message));
+			worker.postMessage({ vendorUrl, wasmBinary });
+		});
+	} catch (error) {
+		worker.terminate();
+		throw error;
+	} finally {
+		URL.revokeObjectURL(workerUrl);
+		URL.revokeObjectURL(vendorUrl);
+	}
+	const pending = new Map();
+	worker.onmessage = (event) => {
+		const result = event.data;
+		pending.get(result.id).resolve(result);
+		pending.delete(result.id);
+	};
+	// The worker can't be used after an uncaught error, fail its jobs instead of leaving them pending.
+	worker.onerror = (event) => {
+		for (const job of pending.values()) {
+			job.reject(new Error(event.message));
+		}
+		pending.clear();
+	};
+	worker.pending = pending;
+	return worker;
+}
+
+// Transcodes the slices `job.firstSlice..job.endSlice` of the texture `job.textureId` in a worker.
+// `job.data` holds the KTX2 data if the worker wasn't sent the texture yet, its buffer is transferred to it.
+// The job also holds the `targetFormat`, `skipLevels`, `maxSize` and `decodeFlags` to prepare the transcoder with.
+//
+// Resolves with `{ data: Uint8Array }` or with `{ error: Uint32Array }` holding the error code, basis format,
+// target format, level, layer, face, width and height, like `TranscodeError`.
+export function transcodeSlices(worker, job) {
+	const id = nextJobId++;
+	const data = job.data ? job.data.buffer : undefined;
+	return new Promise((resolve, reject) => {
+		worker.pending.set(id, { resolve, reject });
+		worker.postMessage({ ...job, id, data }, data ? [data] : []);
+	});
+}
+
+// Frees the KTX2 data of a texture kept by a worker.
+export function releaseTexture(worker, textureId) {
+	worker.postMessage({ releaseTextureId: textureId });
+}
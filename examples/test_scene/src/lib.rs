@@ -26,7 +26,7 @@ pub fn main() {
                     ..Default::default()
                 }),
         )
-        .add_plugins(BasisuLoaderPlugin)
+        .add_plugins(BasisuLoaderPlugin::default())
         .add_systems(Startup, setup)
         .add_systems(Update, rotate_camera)
         .run();
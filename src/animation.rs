@@ -0,0 +1,264 @@
+use core::time::Duration;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_basisu_loader_sys::{Ktx2Transcoder, TranscodeParams, TranscodeState};
+
+use crate::loader::{BasisuLoaderError, BasisuLoaderSettings, TranscodedInfo, prepare_ktx2};
+
+/// The label of the [`BasisuAnimation`] sub-asset of an animated KTX2 texture loaded by [`BasisuLoader`](crate::BasisuLoader).
+///
+/// E.g. `asset_server.load::<BasisuAnimation>("video.basisu_ktx2#animation")`.
+pub const BASISU_ANIMATION_LABEL: &str = "animation";
+
+/// The maximum number of frames a [`BasisuAnimationPlayer`] transcodes per update to catch up with skipped frames.
+const MAX_SEEK_FRAMES_PER_UPDATE: u32 = 8;
+
+/// An animated KTX2 texture, i.e. a flipbook or an ETC1S video with `KTXanimData` key/value data. Each layer is a frame.
+///
+/// The frames are transcoded on demand by a [`BasisuAnimationPlayer`].
+#[derive(Asset, TypePath)]
+pub struct BasisuAnimation {
+    /// The number of frames.
+    pub frames: u32,
+    /// How long each frame is shown.
+    pub frame_duration: Duration,
+    /// How many times the animation is played, or 0 to loop forever.
+    pub loop_count: u32,
+    pub(crate) data: Arc<[u8]>,
+    pub(crate) params: TranscodeParams,
+    pub(crate) settings: BasisuLoaderSettings,
+    pub(crate) info: TranscodedInfo,
+}
+
+impl BasisuAnimation {
+    /// Returns the frame shown after `elapsed` time, or the last frame once all loops are played.
+    pub fn frame_at(&self, elapsed: Duration) -> u32 {
+        if self.frame_duration.is_zero() {
+            return 0;
+        }
+        let frames = u64::from(self.frames.max(1));
+        let index = (elapsed.as_nanos() / self.frame_duration.as_nanos()) as u64;
+        if self.loop_count != 0 && index >= frames * u64::from(self.loop_count) {
+            return (frames - 1) as u32;
+        }
+        (index % frames) as u32
+    }
+
+    /// The total duration of all loops, or `None` if the animation loops forever or the duration overflows [`Duration`].
+    pub fn duration(&self) -> Option<Duration> {
+        if self.loop_count == 0 {
+            return None;
+        }
+        self.frame_duration
+            .checked_mul(self.frames)?
+            .checked_mul(self.loop_count)
+    }
+}
+
+/// The `KTXanimData` value: the frame duration in timescale units, the timescale in units per second and the loop count.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct AnimData {
+    pub(crate) frame_duration: Duration,
+    pub(crate) loop_count: u32,
+}
+
+impl AnimData {
+    pub(crate) fn parse(value: &[u8]) -> Self {
+        let read_u32 = |offset: usize| {
+            value
+                .get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        };
+        let frame_duration = match (read_u32(0), read_u32(4)) {
+            (Some(duration), Some(timescale)) if timescale != 0 => {
+                Duration::from_secs_f64(f64::from(duration) / f64::from(timescale))
+            }
+            _ => {
+                bevy::log::warn!("Invalid KTXanimData, assuming 30 frames per second");
+                Duration::from_secs(1) / 30
+            }
+        };
+        Self {
+            frame_duration,
+            loop_count: read_u32(8).unwrap_or(0),
+        }
+    }
+}
+
+/// Plays a [`BasisuAnimation`] by transcoding its frames into [`Self::image`].
+///
+/// Frames are transcoded in order on the main thread, because the frames of ETC1S video depend on the previous frame.
+/// If frames are skipped, they are still transcoded but not uploaded, at most 8 per update.
+/// The image keeps the previous frame until the player caught up.
+#[derive(Component)]
+pub struct BasisuAnimationPlayer {
+    /// The animation to play.
+    pub animation: Handle<BasisuAnimation>,
+    /// The image that is replaced by the current frame, e.g. the [`Image`] loaded from the same file.
+    pub image: Handle<Image>,
+    /// The playback speed, 1.0 is the speed given by the frame duration.
+    pub speed: f32,
+    /// Whether the playback is paused.
+    pub paused: bool,
+    elapsed: Duration,
+    frame: Option<u32>,
+    decoder: Option<FrameDecoder>,
+}
+
+impl BasisuAnimationPlayer {
+    pub fn new(animation: Handle<BasisuAnimation>, image: Handle<Image>) -> Self {
+        Self {
+            animation,
+            image,
+            speed: 1.0,
+            paused: false,
+            elapsed: Duration::ZERO,
+            frame: None,
+            decoder: None,
+        }
+    }
+
+    /// The time since the playback started, scaled by the speed.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The frame currently shown in the image, if any.
+    pub fn frame(&self) -> Option<u32> {
+        self.frame
+    }
+
+    /// Restarts the playback from the first frame.
+    pub fn restart(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+/// A transcoder prepared for the frames of an animation, with the state carried from frame to frame.
+struct FrameDecoder {
+    animation: AssetId<BasisuAnimation>,
+    transcoder: Ktx2Transcoder,
+    state: TranscodeState,
+    next_frame: u32,
+}
+
+impl FrameDecoder {
+    fn new(
+        id: AssetId<BasisuAnimation>,
+        animation: &BasisuAnimation,
+    ) -> Result<Self, BasisuLoaderError> {
+        let mut transcoder = Ktx2Transcoder::new()?;
+        prepare_ktx2(
+            &mut transcoder,
+            animation.data.clone(),
+            &animation.params,
+            true,
+        )?;
+        Ok(Self {
+            animation: id,
+            transcoder,
+            state: TranscodeState::new()?,
+            next_frame: 0,
+        })
+    }
+
+    /// Transcodes up to [`MAX_SEEK_FRAMES_PER_UPDATE`] frames towards `frame`, starting over from the first frame if
+    /// it was already passed. Returns whether `frame` was reached.
+    fn seek(&mut self, frame: u32) -> Result<bool, BasisuLoaderError> {
+        if frame < self.next_frame.saturating_sub(1) {
+            self.state = TranscodeState::new()?;
+            self.next_frame = 0;
+        }
+        for _ in 0..MAX_SEEK_FRAMES_PER_UPDATE {
+            if self.next_frame > frame {
+                break;
+            }
+            self.transcoder
+                .transcode_frame(self.next_frame, &mut self.state)?;
+            self.next_frame += 1;
+        }
+        Ok(self.next_frame == frame + 1)
+    }
+
+    fn image(&mut self, animation: &BasisuAnimation) -> Image {
+        // The frame was reached by `seek`.
+        let texture = self.transcoder.texture().unwrap();
+        animation.info.to_image(texture.data, &animation.settings)
+    }
+}
+
+/// Advances the [`BasisuAnimationPlayer`]s and replaces their images when the frame changes.
+pub(crate) fn play_basisu_animations(
+    time: Res<Time>,
+    mut animation_events: MessageReader<AssetEvent<BasisuAnimation>>,
+    animations: Res<Assets<BasisuAnimation>>,
+    mut images: ResMut<Assets<Image>>,
+    mut players: Query<&mut BasisuAnimationPlayer>,
+) {
+    // The decoders of reloaded animations are prepared again with the new data.
+    let modified: Vec<_> = animation_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    for mut player in &mut players {
+        if player
+            .decoder
+            .as_ref()
+            .is_some_and(|decoder| modified.contains(&decoder.animation))
+        {
+            player.decoder = None;
+        }
+        let Some(animation) = animations.get(&player.animation) else {
+            continue;
+        };
+        let player = &mut *player;
+        let id = player.animation.id();
+        if player.paused && player.decoder.is_none() {
+            continue;
+        }
+        if player
+            .decoder
+            .as_ref()
+            .is_none_or(|decoder| decoder.animation != id)
+        {
+            player.frame = None;
+            player.decoder = None;
+            match FrameDecoder::new(id, animation) {
+                Ok(decoder) => player.decoder = Some(decoder),
+                Err(err) => {
+                    bevy::log::error!("Failed to prepare the BasisU animation {id}: {err}");
+                    player.paused = true;
+                    continue;
+                }
+            }
+        }
+        let Some(decoder) = &mut player.decoder else {
+            continue;
+        };
+
+        if !player.paused {
+            player.elapsed += time.delta().mul_f32(player.speed.max(0.0));
+        }
+        let frame = animation.frame_at(player.elapsed);
+        if player.frame == Some(frame) {
+            continue;
+        }
+        match decoder.seek(frame) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(err) => {
+                bevy::log::error!(
+                    "Failed to transcode frame {frame} of the BasisU animation {id}: {err}"
+                );
+                player.paused = true;
+                continue;
+            }
+        }
+        let _ = images.insert(&player.image, decoder.image(animation));
+        player.frame = Some(frame);
+    }
+}
@@ -0,0 +1,637 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use bevy::render::render_resource::{TextureFormat, WgpuFeatures};
+use bevy_basisu_loader::{BasisuLoader, BasisuLoaderSettings, UnalignedSizePolicy, is_basisu_ktx2};
+use clap::{Parser, ValueEnum};
+
+/// Prints the KTX2 header, level index, data format descriptor and key/value data of a file, validates it
+/// and prints the format `BasisuLoader` would transcode it to.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// The KTX2 file to inspect.
+    file: PathBuf,
+    /// The simulated wgpu texture compression features, comma separated. Without any, textures are transcoded to uncompressed formats.
+    #[arg(long, value_delimiter = ',')]
+    features: Vec<Feature>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Feature {
+    /// `TEXTURE_COMPRESSION_BC`
+    Bc,
+    /// `TEXTURE_COMPRESSION_ETC2`
+    Etc2,
+    /// `TEXTURE_COMPRESSION_ASTC`
+    Astc,
+    /// `TEXTURE_COMPRESSION_ASTC_HDR`
+    AstcHdr,
+}
+
+impl Feature {
+    fn to_wgpu(self) -> WgpuFeatures {
+        match self {
+            Self::Bc => WgpuFeatures::TEXTURE_COMPRESSION_BC,
+            Self::Etc2 => WgpuFeatures::TEXTURE_COMPRESSION_ETC2,
+            Self::Astc => WgpuFeatures::TEXTURE_COMPRESSION_ASTC,
+            Self::AstcHdr => WgpuFeatures::TEXTURE_COMPRESSION_ASTC_HDR,
+        }
+    }
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const HEADER_LEN: usize = 80;
+const LEVEL_INDEX_ENTRY_LEN: usize = 24;
+
+/// The fields of a KTX2 header, see https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html.
+struct Header {
+    vk_format: u32,
+    type_size: u32,
+    pixel_width: u32,
+    pixel_height: u32,
+    pixel_depth: u32,
+    layer_count: u32,
+    face_count: u32,
+    level_count: u32,
+    supercompression_scheme: u32,
+    dfd: (u64, u64),
+    kvd: (u64, u64),
+    sgd: (u64, u64),
+}
+
+struct Level {
+    byte_offset: u64,
+    byte_length: u64,
+    uncompressed_byte_length: u64,
+}
+
+/// The first block of a data format descriptor.
+struct Dfd {
+    total_size: u32,
+    vendor_id: u32,
+    descriptor_type: u32,
+    version: u32,
+    color_model: u8,
+    color_primaries: u8,
+    transfer_function: u8,
+    flags: u8,
+    texel_block_dimensions: [u8; 4],
+    bytes_planes: [u8; 8],
+    samples: Vec<Sample>,
+}
+
+struct Sample {
+    bit_offset: u32,
+    bit_length: u32,
+    channel_type: u8,
+    lower: u32,
+    upper: u32,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn parse_header(data: &[u8]) -> Option<Header> {
+    let u32_at = |offset| read_u32(data, offset);
+    let u64_at = |offset| read_u64(data, offset);
+    Some(Header {
+        vk_format: u32_at(12)?,
+        type_size: u32_at(16)?,
+        pixel_width: u32_at(20)?,
+        pixel_height: u32_at(24)?,
+        pixel_depth: u32_at(28)?,
+        layer_count: u32_at(32)?,
+        face_count: u32_at(36)?,
+        level_count: u32_at(40)?,
+        supercompression_scheme: u32_at(44)?,
+        dfd: (u32_at(48)? as u64, u32_at(52)? as u64),
+        kvd: (u32_at(56)? as u64, u32_at(60)? as u64),
+        sgd: (u64_at(64)?, u64_at(72)?),
+    })
+}
+
+fn parse_levels(data: &[u8], count: u32) -> Option<Vec<Level>> {
+    (0..count.max(1) as usize)
+        .map(|level| {
+            let offset = HEADER_LEN + level * LEVEL_INDEX_ENTRY_LEN;
+            Some(Level {
+                byte_offset: read_u64(data, offset)?,
+                byte_length: read_u64(data, offset + 8)?,
+                uncompressed_byte_length: read_u64(data, offset + 16)?,
+            })
+        })
+        .collect()
+}
+
+fn parse_dfd(dfd: &[u8]) -> Option<Dfd> {
+    let word = |offset| read_u32(dfd, offset);
+    let block_size = (word(8)? >> 16) as usize;
+    let samples = (24..block_size)
+        .step_by(16)
+        .map(|offset| {
+            let sample = word(offset + 4)?;
+            Some(Sample {
+                bit_offset: sample & 0xffff,
+                bit_length: ((sample >> 16) & 0xff) + 1,
+                channel_type: (sample >> 24) as u8,
+                lower: word(offset + 12)?,
+                upper: word(offset + 16)?,
+            })
+        })
+        .collect::<Option<_>>()?;
+    let bytes = dfd.get(12..28)?;
+    Some(Dfd {
+        total_size: word(0)?,
+        vendor_id: word(4)? & 0x1ffff,
+        descriptor_type: word(4)? >> 17,
+        version: word(8)? & 0xffff,
+        color_model: bytes[0],
+        color_primaries: bytes[1],
+        transfer_function: bytes[2],
+        flags: bytes[3],
+        texel_block_dimensions: bytes[4..8].try_into().unwrap(),
+        bytes_planes: bytes[8..16].try_into().unwrap(),
+        samples,
+    })
+}
+
+/// A key and its value, the value usually is a NUL terminated string.
+type KeyValue<'a> = (&'a [u8], &'a [u8]);
+
+/// Parses the key/value data into pairs, or returns the offset of the first malformed pair.
+fn parse_key_values(kvd: &[u8]) -> Result<Vec<KeyValue<'_>>, usize> {
+    let mut pairs = Vec::new();
+    let mut offset = 0;
+    while offset + 4 <= kvd.len() {
+        let len = read_u32(kvd, offset).unwrap() as usize;
+        let key_value = kvd.get(offset + 4..offset + 4 + len).ok_or(offset)?;
+        let nul = key_value.iter().position(|&b| b == 0).ok_or(offset)?;
+        pairs.push((&key_value[..nul], &key_value[nul + 1..]));
+        offset += 4 + len.next_multiple_of(4);
+    }
+    Ok(pairs)
+}
+
+fn supercompression_name(scheme: u32) -> &'static str {
+    match scheme {
+        0 => "none",
+        1 => "BasisLZ",
+        2 => "Zstandard",
+        3 => "ZLIB",
+        _ => "unknown",
+    }
+}
+
+fn color_model_name(model: u8) -> &'static str {
+    match model {
+        0 => "unspecified",
+        1 => "RGBSDA",
+        128 => "BC1A",
+        129 => "BC2",
+        130 => "BC3",
+        131 => "BC4",
+        132 => "BC5",
+        133 => "BC6H",
+        134 => "BC7",
+        160 => "ETC1",
+        161 => "ETC2",
+        162 => "ASTC",
+        163 => "ETC1S",
+        166 => "UASTC LDR 4x4",
+        167 => "UASTC HDR 4x4",
+        168 => "UASTC HDR 6x6 intermediate",
+        _ => "unknown",
+    }
+}
+
+fn channel_name(color_model: u8, channel_id: u8) -> &'static str {
+    match (color_model, channel_id) {
+        (163, 0) => "RGB",
+        (163, 3) => "RRR",
+        (163, 4) => "GGG",
+        (163, 15) => "AAA",
+        (166..=168, 0) => "RGB",
+        (166..=168, 3) => "RGBA",
+        (166..=168, 4) => "RRR",
+        (166..=168, 5) => "RRRG",
+        (166..=168, 6) => "RG",
+        (1, 0) => "R",
+        (1, 1) => "G",
+        (1, 2) => "B",
+        (1, 13) => "stencil",
+        (1, 14) => "depth",
+        (1, 15) => "A",
+        (128..=162, 0) => "color",
+        (128..=162, 15) => "alpha",
+        _ => "unknown",
+    }
+}
+
+fn transfer_function_name(transfer_function: u8) -> &'static str {
+    match transfer_function {
+        1 => "linear",
+        2 => "sRGB",
+        _ => "other",
+    }
+}
+
+fn color_primaries_name(color_primaries: u8) -> &'static str {
+    match color_primaries {
+        0 => "unspecified",
+        1 => "BT.709",
+        10 => "Display P3",
+        _ => "other",
+    }
+}
+
+/// Formats a key/value value as a string if it's printable, otherwise as its length.
+fn format_value(value: &[u8]) -> String {
+    let text = value.strip_suffix(&[0]).unwrap_or(value);
+    match core::str::from_utf8(text) {
+        Ok(text) if !text.chars().any(|c| c.is_control() && c != '\n') => format!("{text:?}"),
+        _ => format!("<{} bytes>", value.len()),
+    }
+}
+
+/// Collects the problems found in a file.
+#[derive(Default)]
+struct Validation {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Validation {
+    fn error(&mut self, message: impl Into<String>) {
+        self.errors.push(message.into());
+    }
+
+    fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(message.into());
+    }
+
+    /// Checks that a section of the file is inside it.
+    fn check_range(&mut self, name: &str, (offset, len): (u64, u64), file_len: usize) -> bool {
+        if offset
+            .checked_add(len)
+            .is_none_or(|end| end > file_len as u64)
+        {
+            self.error(format!(
+                "{name} ({offset}..{}) is out of the file ({file_len} bytes)",
+                offset.saturating_add(len)
+            ));
+            false
+        } else {
+            true
+        }
+    }
+}
+
+fn validate(data: &[u8], header: &Header, levels: &[Level], validation: &mut Validation) {
+    let max_size = header
+        .pixel_width
+        .max(header.pixel_height)
+        .max(header.pixel_depth);
+    if header.pixel_width == 0 {
+        validation.error("pixelWidth is 0");
+    }
+    if header.pixel_depth != 0 {
+        validation.warn("3D textures aren't supported by BasisuLoader");
+    }
+    if header.face_count != 1 && header.face_count != 6 {
+        validation.error(format!(
+            "faceCount is {}, expected 1 or 6",
+            header.face_count
+        ));
+    } else if header.face_count == 6 && header.pixel_width != header.pixel_height {
+        validation.error("cubemap faces aren't square");
+    }
+    if max_size > 0 && header.level_count > max_size.ilog2() + 1 {
+        validation.error(format!(
+            "levelCount is {}, but a {max_size} pixel texture has at most {} levels",
+            header.level_count,
+            max_size.ilog2() + 1
+        ));
+    }
+    if header.level_count == 0 {
+        validation.warn("levelCount is 0, the loader doesn't generate mipmaps");
+    }
+    if header.supercompression_scheme > 3 {
+        validation.error(format!(
+            "unknown supercompressionScheme {}",
+            header.supercompression_scheme
+        ));
+    }
+    if header.supercompression_scheme == 1 && header.sgd.1 == 0 {
+        validation.error("BasisLZ supercompression without supercompression global data");
+    }
+    if header.vk_format == 0 && header.type_size != 1 {
+        validation.error(format!(
+            "typeSize is {}, expected 1 for an undefined vkFormat",
+            header.type_size
+        ));
+    }
+    let index_end = HEADER_LEN + levels.len() * LEVEL_INDEX_ENTRY_LEN;
+    if header.dfd.0 < index_end as u64 {
+        validation.error("the data format descriptor overlaps the level index");
+    }
+    validation.check_range("the data format descriptor", header.dfd, data.len());
+    validation.check_range("the key/value data", header.kvd, data.len());
+    validation.check_range("the supercompression global data", header.sgd, data.len());
+    if let Some(total_size) = read_u32(data, header.dfd.0 as usize)
+        && total_size as u64 != header.dfd.1
+    {
+        validation.error(format!(
+            "the DFD total size is {total_size}, but dfdByteLength is {}",
+            header.dfd.1
+        ));
+    }
+
+    let mut ranges = Vec::new();
+    for (index, level) in levels.iter().enumerate() {
+        let name = format!("level {index}");
+        if !validation.check_range(&name, (level.byte_offset, level.byte_length), data.len()) {
+            continue;
+        }
+        if level.byte_length == 0 {
+            validation.error(format!("{name} is empty"));
+        }
+        if header.supercompression_scheme == 0
+            && level.uncompressed_byte_length != level.byte_length
+        {
+            validation.error(format!(
+                "{name} isn't supercompressed, but its uncompressed length {} isn't its length {}",
+                level.uncompressed_byte_length, level.byte_length
+            ));
+        }
+        ranges.push((
+            level.byte_offset,
+            level.byte_offset + level.byte_length,
+            index,
+        ));
+    }
+    // The levels are stored from the smallest to the largest, without overlapping.
+    if levels
+        .windows(2)
+        .any(|levels| levels[0].byte_offset < levels[1].byte_offset)
+    {
+        validation.warn("the levels aren't stored from the smallest to the largest");
+    }
+    ranges.sort();
+    for ranges in ranges.windows(2) {
+        if ranges[0].1 > ranges[1].0 {
+            validation.error(format!(
+                "level {} overlaps level {}",
+                ranges[0].2, ranges[1].2
+            ));
+        }
+    }
+}
+
+fn print_header(header: &Header, file_len: usize) {
+    println!("Header ({file_len} bytes)");
+    println!("  vkFormat: {}", header.vk_format);
+    println!("  typeSize: {}", header.type_size);
+    println!(
+        "  pixelWidth x pixelHeight x pixelDepth: {} x {} x {}",
+        header.pixel_width, header.pixel_height, header.pixel_depth
+    );
+    println!("  layerCount: {}", header.layer_count);
+    println!("  faceCount: {}", header.face_count);
+    println!("  levelCount: {}", header.level_count);
+    println!(
+        "  supercompressionScheme: {} ({})",
+        header.supercompression_scheme,
+        supercompression_name(header.supercompression_scheme)
+    );
+    println!("  dfd: offset {}, length {}", header.dfd.0, header.dfd.1);
+    println!("  kvd: offset {}, length {}", header.kvd.0, header.kvd.1);
+    println!("  sgd: offset {}, length {}", header.sgd.0, header.sgd.1);
+}
+
+fn print_levels(header: &Header, levels: &[Level]) {
+    println!("Level index");
+    for (index, level) in levels.iter().enumerate() {
+        println!(
+            "  level {index}: {}x{}, offset {}, length {}, uncompressed length {}",
+            (header.pixel_width >> index).max(1),
+            (header.pixel_height >> index).max(1),
+            level.byte_offset,
+            level.byte_length,
+            level.uncompressed_byte_length
+        );
+    }
+}
+
+fn print_dfd(dfd: &Dfd) {
+    println!("Data format descriptor ({} bytes)", dfd.total_size);
+    println!(
+        "  vendorId: {}, descriptorType: {}, versionNumber: {}",
+        dfd.vendor_id, dfd.descriptor_type, dfd.version
+    );
+    println!(
+        "  colorModel: {} ({})",
+        dfd.color_model,
+        color_model_name(dfd.color_model)
+    );
+    println!(
+        "  colorPrimaries: {} ({})",
+        dfd.color_primaries,
+        color_primaries_name(dfd.color_primaries)
+    );
+    println!(
+        "  transferFunction: {} ({})",
+        dfd.transfer_function,
+        transfer_function_name(dfd.transfer_function)
+    );
+    println!(
+        "  flags: {} ({})",
+        dfd.flags,
+        if dfd.flags & 1 != 0 {
+            "premultiplied alpha"
+        } else {
+            "straight alpha"
+        }
+    );
+    let [x, y, z, w] = dfd
+        .texel_block_dimensions
+        .map(|dimension| dimension as u32 + 1);
+    println!("  texelBlockDimension: {x}x{y}x{z}x{w}");
+    println!("  bytesPlane: {:?}", dfd.bytes_planes);
+    for (index, sample) in dfd.samples.iter().enumerate() {
+        let channel_id = sample.channel_type & 0xf;
+        println!(
+            "  sample {index}: channel {channel_id} ({}), qualifiers {:#x}, bits {}..{}, lower {:#x}, upper {:#x}",
+            channel_name(dfd.color_model, channel_id),
+            sample.channel_type >> 4,
+            sample.bit_offset,
+            sample.bit_offset + sample.bit_length,
+            sample.lower,
+            sample.upper
+        );
+    }
+}
+
+/// Prints the formats the loader would transcode to with the simulated features and flags sizes that aren't a multiple of the block size.
+fn print_transcode_target(
+    data: &[u8],
+    header: &Header,
+    dfd: Option<&Dfd>,
+    features: &[Feature],
+    validation: &mut Validation,
+) {
+    let wgpu_features = features.iter().fold(WgpuFeatures::empty(), |all, feature| {
+        all | feature.to_wgpu()
+    });
+    println!("Transcoding (features: {features:?})");
+    // SAFETY: The transcoder is initialized once before any transcoder is created.
+    unsafe { bevy_basisu_loader_sys::basisu_transcoder_init() };
+    let loader = BasisuLoader::from_features(wgpu_features);
+    let compressed = loader.transcode_target(
+        data,
+        &BasisuLoaderSettings {
+            unaligned_size: UnalignedSizePolicy::Pad,
+            ..Default::default()
+        },
+    );
+    let target = loader.transcode_target(data, &BasisuLoaderSettings::default());
+    match (&compressed, &target) {
+        (Ok(compressed), Ok(target)) if compressed != target => {
+            println!("  target: {target:?} (falls back from {compressed:?})");
+        }
+        (_, Ok(target)) => println!("  target: {target:?}"),
+        (_, Err(error)) => {
+            validation.error(format!("BasisuLoader can't load the file: {error}"));
+            return;
+        }
+    }
+
+    // The loader needs the base level to be a multiple of the block size of the target, see `UnalignedSizePolicy`.
+    let (width, height) = (header.pixel_width, header.pixel_height);
+    let unaligned = |(block_width, block_height): (u32, u32)| {
+        !width.is_multiple_of(block_width) || !height.is_multiple_of(block_height)
+    };
+    if let Ok(compressed) = compressed
+        && unaligned(compressed.block_dimensions())
+    {
+        let (block_width, block_height) = compressed.block_dimensions();
+        validation.warn(format!(
+            "{width}x{height} isn't a multiple of the {block_width}x{block_height} block size of {compressed:?}, \
+            the loader transcodes it to an uncompressed format unless UnalignedSizePolicy::Pad is set"
+        ));
+    }
+    // Other devices may pick a target with another block size, e.g. BC6H instead of ASTC 6x6.
+    let mut block_sizes = vec![(4, 4)];
+    if let Some(dfd) = dfd {
+        let [x, y, ..] = dfd
+            .texel_block_dimensions
+            .map(|dimension| dimension as u32 + 1);
+        if (x, y) != (1, 1) && (x, y) != (4, 4) {
+            block_sizes.push((x, y));
+        }
+    }
+    for (block_width, block_height) in block_sizes {
+        if unaligned((block_width, block_height))
+            && compressed
+                .as_ref()
+                .ok()
+                .map(TextureFormat::block_dimensions)
+                != Some((block_width, block_height))
+        {
+            validation.warn(format!(
+                "{width}x{height} isn't a multiple of {block_width}x{block_height}, devices with {block_width}x{block_height} block targets transcode it to an uncompressed format"
+            ));
+        }
+    }
+}
+
+fn inspect(data: &[u8], args: &Args) -> Validation {
+    let mut validation = Validation::default();
+    if !data.starts_with(&KTX2_IDENTIFIER) {
+        validation.error("not a KTX2 file, the identifier doesn't match");
+        return validation;
+    }
+    let Some(header) = parse_header(data) else {
+        validation.error("the header is truncated");
+        return validation;
+    };
+    print_header(&header, data.len());
+
+    let Some(levels) = parse_levels(data, header.level_count) else {
+        validation.error("the level index is truncated");
+        return validation;
+    };
+    print_levels(&header, &levels);
+
+    let dfd = data
+        .get(header.dfd.0 as usize..(header.dfd.0 + header.dfd.1) as usize)
+        .and_then(parse_dfd);
+    match &dfd {
+        Some(dfd) => print_dfd(dfd),
+        None => validation.error("the data format descriptor is malformed"),
+    }
+
+    println!("Key/value data");
+    let kvd = data
+        .get(header.kvd.0 as usize..(header.kvd.0 + header.kvd.1) as usize)
+        .unwrap_or_default();
+    match parse_key_values(kvd) {
+        Ok(pairs) => {
+            for (key, value) in &pairs {
+                println!(
+                    "  {}: {}",
+                    String::from_utf8_lossy(key),
+                    format_value(value)
+                );
+            }
+            if pairs.windows(2).any(|pairs| pairs[0].0 > pairs[1].0) {
+                validation.warn("the keys aren't sorted");
+            }
+        }
+        Err(offset) => validation.error(format!("the key/value pair at {offset} is malformed")),
+    }
+
+    validate(data, &header, &levels, &mut validation);
+    if is_basisu_ktx2(data) {
+        if validation.errors.is_empty() {
+            print_transcode_target(data, &header, dfd.as_ref(), &args.features, &mut validation);
+        }
+    } else {
+        println!("Not a Basis Universal texture, it's loaded by Bevy's ImageLoader");
+    }
+    validation
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let data = match std::fs::read(&args.file) {
+        Ok(data) => data,
+        Err(error) => {
+            eprintln!("Couldn't read {}: {error}", args.file.display());
+            return ExitCode::FAILURE;
+        }
+    };
+    let validation = inspect(&data, &args);
+    println!("Validation");
+    for warning in &validation.warnings {
+        println!("  warning: {warning}");
+    }
+    for error in &validation.errors {
+        println!("  error: {error}");
+    }
+    if validation.errors.is_empty() {
+        println!("  ok");
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
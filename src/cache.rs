@@ -0,0 +1,191 @@
+use bevy_basisu_loader_sys::{TextureLayout, TranscodeParams};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+const CACHE_FILE_EXTENSION: &str = "transcoded";
+
+/// A persistent cache of transcoded KTX2 textures, see [`BasisuLoaderPlugin::transcode_cache`](crate::BasisuLoaderPlugin::transcode_cache).
+///
+/// Each file holds the transcoded mip chain of a texture, keyed by the hash of the KTX2 file, the transcode target,
+/// the settings that change the transcoded data and the version of this crate. Textures loaded from the cache aren't transcoded again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscodeCache {
+    /// The directory of the cache files. It's created if it doesn't exist.
+    pub directory: PathBuf,
+    /// The maximum total size of the cache files in bytes. The least recently used files are removed when it's exceeded.
+    pub max_size: u64,
+}
+
+impl TranscodeCache {
+    /// A cache in `directory` with a maximum size of 1 GiB.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            max_size: 1 << 30,
+        }
+    }
+
+    /// Returns the name of the cache file of a prepared texture.
+    ///
+    /// The hash has to be the same across runs, platforms and Rust releases, and its name is part of the key so that changing it can't mix up files.
+    pub(crate) fn key(data: &[u8], params: &TranscodeParams, layout: &TextureLayout) -> String {
+        let mut hasher = Fnv1a128::default();
+        let version = env!("CARGO_PKG_VERSION");
+        hasher.write(&(version.len() as u64).to_le_bytes());
+        hasher.write(version.as_bytes());
+        hasher.write(&(data.len() as u64).to_le_bytes());
+        hasher.write(data);
+        hasher.write(&layout.target_format.0.to_le_bytes());
+        hasher.write(&layout.levels.to_le_bytes());
+        hasher.write(&params.skip_levels.to_le_bytes());
+        hasher.write(&params.max_size.to_le_bytes());
+        hasher.write(&params.decode_flags.0.to_le_bytes());
+        format!(
+            "{}-{:032x}.{CACHE_FILE_EXTENSION}",
+            Fnv1a128::NAME,
+            hasher.finish()
+        )
+    }
+
+    /// Reads the transcoded data of a key if it's cached and `len` bytes long, and marks it as recently used.
+    pub(crate) fn read(&self, key: &str, len: usize) -> Option<Vec<u8>> {
+        let path = self.directory.join(key);
+        let data = std::fs::read(&path).ok()?;
+        if data.len() != len {
+            bevy::log::warn!("Removing the transcode cache file {path:?} with an unexpected size");
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        if let Err(err) = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+        {
+            bevy::log::warn!("Failed to update the transcode cache file {path:?}: {err}");
+        }
+        Some(data)
+    }
+
+    /// Writes the transcoded data of a key, then evicts the least recently used files if the cache is too large.
+    pub(crate) fn write(&self, key: &str, data: &[u8]) {
+        if data.len() as u64 > self.max_size {
+            return;
+        }
+        let path = self.directory.join(key);
+        // Written to a temporary file first, so that concurrent loads never read a partial file.
+        let temp_path = path.with_extension("tmp");
+        let result = std::fs::create_dir_all(&self.directory)
+            .and_then(|()| std::fs::write(&temp_path, data))
+            .and_then(|()| std::fs::rename(&temp_path, &path));
+        if let Err(err) = result {
+            bevy::log::warn!("Failed to write the transcode cache file {path:?}: {err}");
+            let _ = std::fs::remove_file(&temp_path);
+            return;
+        }
+        self.evict();
+    }
+
+    /// Removes the least recently used files until the cache fits into [`Self::max_size`].
+    fn evict(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.directory) else {
+            return;
+        };
+        let mut files = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let path = entry.path();
+                if path.extension()? != CACHE_FILE_EXTENSION {
+                    return None;
+                }
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), path))
+            })
+            .collect::<Vec<_>>();
+        let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+        files.sort();
+        for (_, len, path) in files {
+            if size <= self.max_size {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                size -= len;
+            }
+        }
+    }
+}
+
+/// The 128-bit FNV-1a hash. It's specified bit for bit, unlike [`DefaultHasher`](std::hash::DefaultHasher).
+struct Fnv1a128(u128);
+
+impl Fnv1a128 {
+    const NAME: &str = "fnv1a128";
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u128::from(byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u128 {
+        self.0
+    }
+}
+
+impl Default for Fnv1a128 {
+    fn default() -> Self {
+        Self(0x6c62272e07bb014262b821756295c58d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn fnv1a128_matches_reference() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv1a128::default();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(hash(b""), 0x6c62272e07bb014262b821756295c58d);
+        assert_eq!(hash(b"a"), 0xd228cb696f1a8caf78912b704e4a8964);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let directory = std::env::temp_dir().join(format!(
+            "bevy_basisu_loader_cache_test_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        let cache = TranscodeCache {
+            directory: directory.clone(),
+            max_size: 20,
+        };
+        cache.write("a.transcoded", &[1; 8]);
+        cache.write("b.transcoded", &[2; 8]);
+        assert_eq!(cache.read("a.transcoded", 8), Some(vec![1; 8]));
+        assert_eq!(cache.read("b.transcoded", 4), None);
+
+        // The modification time is the access time, make `a` older than `c`.
+        let past = SystemTime::now() - Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(directory.join("a.transcoded"))
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+        cache.write("c.transcoded", &[3; 8]);
+        cache.write("d.transcoded", &[4; 8]);
+        assert_eq!(cache.read("a.transcoded", 8), None);
+        assert_eq!(cache.read("c.transcoded", 8), Some(vec![3; 8]));
+        assert_eq!(cache.read("d.transcoded", 8), Some(vec![4; 8]));
+
+        cache.write("e.transcoded", &[5; 32]);
+        assert_eq!(cache.read("e.transcoded", 32), None);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use bevy::image::Image;
+use bevy::render::render_resource::{AstcBlock, AstcChannel, TextureFormat};
+use thiserror::Error;
+
+mod astc;
+mod bc;
+mod etc;
+
+/// Pixels decoded by [`decode_to_rgba`], 4 channels per pixel in row-major order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedPixels {
+    /// Decoded from an LDR format. The values are the stored ones, sRGB formats aren't converted to linear.
+    Rgba8(Vec<u8>),
+    /// Decoded from an HDR format (BC6H, ASTC HDR, `Rgba16Float` or `Rgb9e5Ufloat`).
+    Rgba32F(Vec<f32>),
+}
+
+/// An error when decoding pixels with [`decode_to_rgba`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    /// The format isn't one that [`BasisuLoader`](crate::BasisuLoader) transcodes to.
+    #[error("Decoding {0:?} textures isn't supported")]
+    UnsupportedFormat(TextureFormat),
+    /// The data is shorter than a surface of the given size.
+    #[error("Expected {expected} bytes of texture data, got {actual}")]
+    DataTooShort { expected: usize, actual: usize },
+    /// The [`Image`] has no data, e.g. because it was only kept in the render world.
+    #[error("The image has no data in the main world")]
+    NoData,
+}
+
+/// Decodes a 2D surface of a texture format that [`BasisuLoader`](crate::BasisuLoader) transcodes to on the CPU.
+///
+/// `data` is a single mip level of a single layer and face, e.g. the start of [`Image::data`]. Block compressed data is padded to whole blocks.
+/// BC1/BC3/BC4/BC5/BC7, ETC2/EAC, ASTC 4x4 LDR and `Rgba8Unorm` are decoded to [`DecodedPixels::Rgba8`],
+/// BC6H, ASTC 4x4/6x6 HDR, `Rgba16Float` and `Rgb9e5Ufloat` to [`DecodedPixels::Rgba32F`].
+/// Single and dual channel formats are decoded like they are sampled, e.g. BC4 as `(r, 0, 0, 1)`.
+pub fn decode_to_rgba(
+    data: &[u8],
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+) -> Result<DecodedPixels, DecodeError> {
+    let format = format.remove_srgb_suffix();
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format
+        .block_copy_size(None)
+        .ok_or(DecodeError::UnsupportedFormat(format))? as usize;
+    let expected =
+        width.div_ceil(block_width) as usize * height.div_ceil(block_height) as usize * block_size;
+    let Some(data) = data.get(..expected) else {
+        return Err(DecodeError::DataTooShort {
+            expected,
+            actual: data.len(),
+        });
+    };
+    let ldr = |decode_block: fn(&[u8], &mut [[u8; 4]])| {
+        DecodedPixels::Rgba8(decode_blocks(data, format, width, height, decode_block))
+    };
+    let hdr = |decode_block: fn(&[u8], &mut [[f32; 4]])| {
+        DecodedPixels::Rgba32F(decode_blocks(data, format, width, height, decode_block))
+    };
+    Ok(match format {
+        TextureFormat::Rgba8Unorm => DecodedPixels::Rgba8(data.to_vec()),
+        TextureFormat::Rgba16Float => DecodedPixels::Rgba32F(
+            data.chunks_exact(2)
+                .map(|half| f16_to_f32(u16::from_le_bytes([half[0], half[1]])))
+                .collect(),
+        ),
+        TextureFormat::Rgb9e5Ufloat => hdr(decode_rgb9e5),
+        TextureFormat::Bc1RgbaUnorm => ldr(bc::decode_bc1),
+        TextureFormat::Bc3RgbaUnorm => ldr(bc::decode_bc3),
+        TextureFormat::Bc4RUnorm => ldr(bc::decode_bc4),
+        TextureFormat::Bc5RgUnorm => ldr(bc::decode_bc5),
+        TextureFormat::Bc6hRgbUfloat => hdr(bc::decode_bc6h),
+        TextureFormat::Bc7RgbaUnorm => ldr(bc::decode_bc7),
+        TextureFormat::Etc2Rgb8Unorm => ldr(etc::decode_etc2_rgb),
+        TextureFormat::Etc2Rgba8Unorm => ldr(etc::decode_etc2_rgba),
+        TextureFormat::EacR11Unorm => ldr(etc::decode_eac_r11),
+        TextureFormat::EacRg11Unorm => ldr(etc::decode_eac_rg11),
+        TextureFormat::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::Unorm,
+        } => ldr(astc::decode_ldr_4x4),
+        TextureFormat::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::Hdr,
+        } => hdr(astc::decode_hdr_4x4),
+        TextureFormat::Astc {
+            block: AstcBlock::B6x6,
+            channel: AstcChannel::Hdr,
+        } => hdr(astc::decode_hdr_6x6),
+        _ => return Err(DecodeError::UnsupportedFormat(format)),
+    })
+}
+
+/// Decodes the first mip level of an [`Image`] loaded by [`BasisuLoader`](crate::BasisuLoader), see [`decode_to_rgba`].
+///
+/// The layers and faces of array and cube textures follow each other, like in [`Image::data`].
+pub fn decode_image_to_rgba(image: &Image) -> Result<DecodedPixels, DecodeError> {
+    let data = image.data.as_deref().ok_or(DecodeError::NoData)?;
+    let format = image.texture_descriptor.format;
+    let size = image.texture_descriptor.size;
+    let layers = size.depth_or_array_layers as usize;
+    // Each layer is padded to whole blocks, so they are decoded separately.
+    let (block_width, block_height) = format.block_dimensions();
+    let layer_size = size.width.div_ceil(block_width) as usize
+        * size.height.div_ceil(block_height) as usize
+        * format.block_copy_size(None).unwrap_or(0) as usize;
+    let mut decoded = None;
+    for layer in 0..layers {
+        let pixels = decode_to_rgba(
+            data.get(layer * layer_size..).unwrap_or_default(),
+            format,
+            size.width,
+            size.height,
+        )?;
+        decoded = Some(match (decoded, pixels) {
+            (None, pixels) => pixels,
+            (Some(DecodedPixels::Rgba8(mut all)), DecodedPixels::Rgba8(pixels)) => {
+                all.extend(pixels);
+                DecodedPixels::Rgba8(all)
+            }
+            (Some(DecodedPixels::Rgba32F(mut all)), DecodedPixels::Rgba32F(pixels)) => {
+                all.extend(pixels);
+                DecodedPixels::Rgba32F(all)
+            }
+            _ => unreachable!("all layers have the same format"),
+        });
+    }
+    Ok(decoded.unwrap_or(DecodedPixels::Rgba8(Vec::new())))
+}
+
+/// Decodes the blocks of a surface and copies the pixels inside the surface to the output.
+fn decode_blocks<T: Copy + Default>(
+    data: &[u8],
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    decode_block: fn(&[u8], &mut [[T; 4]]),
+) -> Vec<T> {
+    let (block_width, block_height) = format.block_dimensions();
+    let (block_width, block_height) = (block_width as usize, block_height as usize);
+    let block_size = format.block_copy_size(None).unwrap() as usize;
+    let (width, height) = (width as usize, height as usize);
+    let blocks_x = width.div_ceil(block_width);
+    let mut pixels = vec![T::default(); width * height * 4];
+    let mut block_pixels = vec![[T::default(); 4]; block_width * block_height];
+    for (index, block) in data.chunks_exact(block_size).enumerate() {
+        decode_block(block, &mut block_pixels);
+        let (x0, y0) = (
+            index % blocks_x * block_width,
+            index / blocks_x * block_height,
+        );
+        for y in y0..(y0 + block_height).min(height) {
+            for x in x0..(x0 + block_width).min(width) {
+                let pixel = block_pixels[(y - y0) * block_width + x - x0];
+                pixels[(y * width + x) * 4..][..4].copy_from_slice(&pixel);
+            }
+        }
+    }
+    pixels
+}
+
+fn decode_rgb9e5(block: &[u8], pixels: &mut [[f32; 4]]) {
+    let packed = u32::from_le_bytes(block.try_into().unwrap());
+    let scale = 2f32.powi((packed >> 27) as i32 - 15 - 9);
+    let [r, g, b] = [0, 9, 18].map(|shift| ((packed >> shift) & 0x1ff) as f32 * scale);
+    pixels[0] = [r, g, b, 1.0];
+}
+
+/// Converts the bits of a half float.
+pub(crate) fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (half >> 10) & 0x1f;
+    let mantissa = (half & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent as i32 - 15),
+    }
+}
+
+/// Reads little-endian bit fields of a block, starting at the least significant bit of the first byte.
+pub(crate) struct BitReader {
+    bits: u128,
+    offset: u32,
+}
+
+impl BitReader {
+    pub(crate) fn new(block: &[u8]) -> Self {
+        Self {
+            bits: u128::from_le_bytes(block.try_into().unwrap()),
+            offset: 0,
+        }
+    }
+
+    pub(crate) fn read(&mut self, count: u32) -> u32 {
+        let value = bits(self.bits, self.offset, count);
+        self.offset += count;
+        value
+    }
+}
+
+/// Returns `count` bits of `value` starting at bit `offset`.
+pub(crate) fn bits(value: u128, offset: u32, count: u32) -> u32 {
+    // Reads past the end of the block, e.g. of partial integer sequences, return zeros.
+    (value.checked_shr(offset).unwrap_or(0) & ((1 << count) - 1)) as u32
+}
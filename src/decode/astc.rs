@@ -0,0 +1,1096 @@
+//! A decoder of ASTC 2D blocks with the LDR and HDR profiles, see the Khronos Data Format Specification.
+
+use super::{BitReader, bits, f16_to_f32};
+
+/// The color of blocks that are invalid or use HDR in the LDR profile.
+const ERROR_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+
+pub(super) fn decode_ldr_4x4(block: &[u8], pixels: &mut [[u8; 4]]) {
+    let mut texels = [[0.0; 4]; 16];
+    decode_block(block, 4, 4, false, &mut texels);
+    for (pixel, texel) in pixels.iter_mut().zip(texels) {
+        *pixel = texel.map(|c| (c * 255.0).round() as u8);
+    }
+}
+
+pub(super) fn decode_hdr_4x4(block: &[u8], pixels: &mut [[f32; 4]]) {
+    decode_block(block, 4, 4, true, pixels);
+}
+
+pub(super) fn decode_hdr_6x6(block: &[u8], pixels: &mut [[f32; 4]]) {
+    decode_block(block, 6, 6, true, pixels);
+}
+
+fn decode_block(block: &[u8], width: usize, height: usize, hdr: bool, texels: &mut [[f32; 4]]) {
+    if try_decode_block(block, width, height, hdr, texels).is_none() {
+        texels.fill(ERROR_COLOR);
+    }
+}
+
+/// The number of values of the integer sequence encoding ranges, in increasing order.
+const RANGES: [u32; 21] = [
+    2, 3, 4, 5, 6, 8, 10, 12, 16, 20, 24, 32, 40, 48, 64, 80, 96, 128, 160, 192, 256,
+];
+
+/// The encoding of a range, whether it uses a trit or quint per value and the number of bits per value.
+fn range_encoding(levels: u32) -> (bool, bool, u32) {
+    if levels.is_multiple_of(3) {
+        (true, false, (levels / 3).trailing_zeros())
+    } else if levels.is_multiple_of(5) {
+        (false, true, (levels / 5).trailing_zeros())
+    } else {
+        (false, false, levels.trailing_zeros())
+    }
+}
+
+/// The number of bits of `count` values encoded with the integer sequence encoding.
+fn ise_bits(count: u32, levels: u32) -> u32 {
+    match range_encoding(levels) {
+        (true, _, bits) => count * bits + (count * 8).div_ceil(5),
+        (_, true, bits) => count * bits + (count * 7).div_ceil(3),
+        (_, _, bits) => count * bits,
+    }
+}
+
+/// Decodes values with the integer sequence encoding, returning the trit or quint and the bits of each value.
+fn decode_ise(data: u128, levels: u32, values: &mut [(u32, u32)]) {
+    let (trits, quints, bits) = range_encoding(levels);
+    let mut reader = BitReader {
+        bits: data,
+        offset: 0,
+    };
+    let count = values.len();
+    let mut write = |index: usize, value: (u32, u32)| {
+        if let Some(slot) = values.get_mut(index) {
+            *slot = value;
+        }
+    };
+    let mut index = 0;
+    while index < count {
+        if trits {
+            let mut m = [0; 5];
+            let mut t = 0;
+            for (i, (shift, count)) in [(0, 2), (2, 2), (4, 1), (5, 2), (7, 1)]
+                .into_iter()
+                .enumerate()
+            {
+                m[i] = reader.read(bits);
+                t |= reader.read(count) << shift;
+            }
+            for (i, trit) in decode_trits(t).into_iter().enumerate() {
+                write(index + i, (trit, m[i]));
+            }
+            index += 5;
+        } else if quints {
+            let mut m = [0; 3];
+            let mut q = 0;
+            for (i, (shift, count)) in [(0, 3), (3, 2), (5, 2)].into_iter().enumerate() {
+                m[i] = reader.read(bits);
+                q |= reader.read(count) << shift;
+            }
+            for (i, quint) in decode_quints(q).into_iter().enumerate() {
+                write(index + i, (quint, m[i]));
+            }
+            index += 3;
+        } else {
+            write(index, (0, reader.read(bits)));
+            index += 1;
+        }
+    }
+}
+
+fn decode_trits(t: u32) -> [u32; 5] {
+    let bit = |i: u32| t >> i & 1;
+    let (c, t4, t3);
+    if t >> 2 & 7 == 7 {
+        c = (t >> 5 & 7) << 2 | (t & 3);
+        t4 = 2;
+        t3 = 2;
+    } else {
+        c = t & 0x1f;
+        if t >> 5 & 3 == 3 {
+            t4 = 2;
+            t3 = bit(7);
+        } else {
+            t4 = bit(7);
+            t3 = t >> 5 & 3;
+        }
+    }
+    let c_bit = |i: u32| c >> i & 1;
+    let (t2, t1, t0);
+    if c & 3 == 3 {
+        t2 = 2;
+        t1 = c_bit(4);
+        t0 = c_bit(3) << 1 | (c_bit(2) & !c_bit(3) & 1);
+    } else if c >> 2 & 3 == 3 {
+        t2 = 2;
+        t1 = 2;
+        t0 = c & 3;
+    } else {
+        t2 = c_bit(4);
+        t1 = c >> 2 & 3;
+        t0 = c_bit(1) << 1 | (c_bit(0) & !c_bit(1) & 1);
+    }
+    [t0, t1, t2, t3, t4]
+}
+
+fn decode_quints(q: u32) -> [u32; 3] {
+    let bit = |i: u32| q >> i & 1;
+    if q >> 1 & 3 == 3 && q >> 5 & 3 == 0 {
+        let q2 = bit(0) << 2 | (bit(4) & !bit(0) & 1) << 1 | (bit(3) & !bit(0) & 1);
+        return [4, 4, q2];
+    }
+    let (q2, c) = if q >> 1 & 3 == 3 {
+        (4, (q >> 3 & 3) << 3 | (!(q >> 5) & 3) << 1 | bit(0))
+    } else {
+        (q >> 5 & 3, q & 0x1f)
+    };
+    if c & 7 == 5 {
+        [c >> 3 & 3, 4, q2]
+    } else {
+        [c & 7, c >> 3 & 3, q2]
+    }
+}
+
+/// Unquantizes a color endpoint value to 0..=255.
+fn unquantize_color(levels: u32, (d, m): (u32, u32)) -> i32 {
+    let (trits, quints, bits) = range_encoding(levels);
+    if !trits && !quints {
+        // Replicate the bits to 8 bits.
+        let mut value = m << (8 - bits);
+        let mut filled = bits;
+        while filled < 8 {
+            value |= value >> filled;
+            filled *= 2;
+        }
+        return value as i32;
+    }
+    let bit = |i: u32| m >> i & 1;
+    let (b, c) = match (trits, bits) {
+        (true, 1) => (0, 204),
+        (true, 2) => (bit(1) * 0b100010110, 93),
+        (true, 3) => ((bit(2) * 0b100001010) | (bit(1) * 0b010000101), 44),
+        (true, 4) => (
+            (bit(3) * 0b100000100) | (bit(2) * 0b010000010) | (bit(1) * 0b001000001),
+            22,
+        ),
+        (true, 5) => (
+            (bit(4) * 0b100000010)
+                | (bit(3) * 0b010000001)
+                | (bit(2) * 0b001000000)
+                | (bit(1) * 0b000100000),
+            11,
+        ),
+        (true, _) => (
+            (bit(5) * 0b100000001)
+                | (bit(4) * 0b010000000)
+                | (bit(3) * 0b001000000)
+                | (bit(2) * 0b000100000)
+                | (bit(1) * 0b000010000),
+            5,
+        ),
+        (_, 1) => (0, 113),
+        (_, 2) => (bit(1) * 0b100001100, 54),
+        (_, 3) => ((bit(2) * 0b100000101) | (bit(1) * 0b010000010), 26),
+        (_, 4) => (
+            (bit(3) * 0b100000010) | (bit(2) * 0b010000001) | (bit(1) * 0b001000000),
+            13,
+        ),
+        (_, _) => (
+            (bit(4) * 0b100000001)
+                | (bit(3) * 0b010000000)
+                | (bit(2) * 0b001000000)
+                | (bit(1) * 0b000100000),
+            6,
+        ),
+    };
+    let a = bit(0) * 0x1ff;
+    let t = (d * c + b) ^ a;
+    ((a & 0x80) | t >> 2) as i32
+}
+
+/// Unquantizes a weight to 0..=64.
+fn unquantize_weight(levels: u32, (d, m): (u32, u32)) -> u32 {
+    let (trits, quints, bits) = range_encoding(levels);
+    let value = if !trits && !quints {
+        // Replicate the bits to 6 bits.
+        let mut value = m << (6 - bits);
+        let mut filled = bits;
+        while filled < 6 {
+            value |= value >> filled;
+            filled *= 2;
+        }
+        value
+    } else if bits == 0 {
+        if trits {
+            [0, 32, 63][d as usize]
+        } else {
+            [0, 16, 32, 47, 63][d as usize]
+        }
+    } else {
+        let bit = |i: u32| m >> i & 1;
+        let (b, c) = match (trits, bits) {
+            (true, 1) => (0, 50),
+            (true, 2) => (bit(1) * 0b1000101, 23),
+            (true, _) => ((bit(2) * 0b1000010) | (bit(1) * 0b0100001), 11),
+            (_, 1) => (0, 28),
+            (_, _) => (bit(1) * 0b1000010, 13),
+        };
+        let a = bit(0) * 0x7f;
+        let t = (d * c + b) ^ a;
+        (a & 0x20) | t >> 2
+    };
+    if value > 32 { value + 1 } else { value }
+}
+
+/// The weight grid of a block mode.
+struct BlockMode {
+    grid_width: usize,
+    grid_height: usize,
+    weight_levels: u32,
+    dual_plane: bool,
+}
+
+impl BlockMode {
+    fn decode(mode: u32) -> Option<Self> {
+        let a = mode >> 5 & 3;
+        let b = mode >> 7 & 3;
+        let mut high_precision = mode >> 9 & 1 == 1;
+        let mut dual_plane = mode >> 10 & 1 == 1;
+        let (range, grid_width, grid_height);
+        if mode & 3 != 0 {
+            range = (mode >> 4 & 1) | (mode & 3) << 1;
+            (grid_width, grid_height) = match mode >> 2 & 3 {
+                0 => (b + 4, a + 2),
+                1 => (b + 8, a + 2),
+                2 => (a + 2, b + 8),
+                _ if mode >> 8 & 1 == 0 => (a + 2, (b & 1) + 6),
+                _ => ((b & 1) + 2, a + 2),
+            };
+        } else {
+            range = (mode >> 4 & 1) | (mode >> 1 & 6);
+            if mode & 0xf == 0 {
+                return None;
+            }
+            (grid_width, grid_height) = match b {
+                0 => (12, a + 2),
+                1 => (a + 2, 12),
+                2 => {
+                    // The high precision and dual plane bits are part of the grid height.
+                    high_precision = false;
+                    dual_plane = false;
+                    (a + 6, (mode >> 9 & 3) + 6)
+                }
+                _ => match a {
+                    0 => (6, 10),
+                    1 => (10, 6),
+                    _ => return None,
+                },
+            };
+        }
+        if range < 2 {
+            return None;
+        }
+        Some(Self {
+            grid_width: grid_width as usize,
+            grid_height: grid_height as usize,
+            weight_levels: RANGES[(range - 2 + high_precision as u32 * 6) as usize],
+            dual_plane,
+        })
+    }
+}
+
+/// The color endpoints of a partition as 16 bit values, UNORM16 for LDR and logarithmic for HDR channels.
+#[derive(Clone, Copy, Default)]
+struct Endpoints {
+    e0: [i32; 4],
+    e1: [i32; 4],
+    rgb_hdr: bool,
+    alpha_hdr: bool,
+}
+
+fn try_decode_block(
+    block: &[u8],
+    width: usize,
+    height: usize,
+    hdr: bool,
+    texels: &mut [[f32; 4]],
+) -> Option<()> {
+    let data = u128::from_le_bytes(block.try_into().unwrap());
+    let field = |offset: u32, count: u32| bits(data, offset, count);
+    let mode = field(0, 11);
+
+    if mode & 0x1ff == 0x1fc {
+        // A void-extent block has a single color, FP16 if HDR and UNORM16 otherwise.
+        let is_hdr = mode & 0x200 != 0;
+        if is_hdr && !hdr {
+            return None;
+        }
+        let color: [f32; 4] = core::array::from_fn(|c| {
+            let value = field(64 + 16 * c as u32, 16) as u16;
+            if is_hdr {
+                f16_to_f32(value)
+            } else {
+                value as f32 / 65535.0
+            }
+        });
+        texels.fill(color);
+        return Some(());
+    }
+
+    let BlockMode {
+        grid_width,
+        grid_height,
+        weight_levels,
+        dual_plane,
+    } = BlockMode::decode(mode)?;
+    let planes = if dual_plane { 2 } else { 1 };
+    let weight_count = grid_width * grid_height * planes;
+    if grid_width > width || grid_height > height || weight_count > 64 {
+        return None;
+    }
+    let weight_bits = ise_bits(weight_count as u32, weight_levels);
+    if !(24..=96).contains(&weight_bits) {
+        return None;
+    }
+
+    let partitions = field(11, 2) as usize + 1;
+    if dual_plane && partitions == 4 {
+        return None;
+    }
+    let mut cems = [0; 4];
+    let mut extra_cem_bits = 0;
+    let (partition_seed, color_start) = if partitions == 1 {
+        cems[0] = field(13, 4);
+        (0, 17)
+    } else {
+        let cem = field(23, 6);
+        let class = cem & 3;
+        if class == 0 {
+            cems = [cem >> 2; 4];
+        } else {
+            // The remaining bits of the endpoint modes are stored below the weights.
+            extra_cem_bits = 3 * partitions as u32 - 4;
+            let extra = field(128 - weight_bits - extra_cem_bits, extra_cem_bits);
+            let cem = cem >> 2 | extra << 4;
+            for (i, mode) in cems[..partitions].iter_mut().enumerate() {
+                let class = class - 1 + (cem >> i & 1);
+                *mode = class << 2 | (cem >> (partitions + 2 * i) & 3);
+            }
+        }
+        (field(13, 10), 29)
+    };
+    let color_end = 128 - weight_bits - extra_cem_bits - if dual_plane { 2 } else { 0 };
+    let color_component_selector = if dual_plane {
+        field(color_end, 2) as usize
+    } else {
+        4
+    };
+
+    let value_count: usize = cems[..partitions]
+        .iter()
+        .map(|&cem| (cem as usize / 4 + 1) * 2)
+        .sum();
+    if value_count > 18 || color_end <= color_start {
+        return None;
+    }
+    let color_bits = color_end - color_start;
+    let color_levels = *RANGES[4..]
+        .iter()
+        .rev()
+        .find(|&&levels| ise_bits(value_count as u32, levels) <= color_bits)?;
+    let mut color_values = [(0, 0); 18];
+    decode_ise(
+        (data >> color_start) & ((1 << color_bits) - 1),
+        color_levels,
+        &mut color_values[..value_count],
+    );
+    let mut endpoints = [Endpoints::default(); 4];
+    let mut values = color_values
+        .iter()
+        .map(|&value| unquantize_color(color_levels, value));
+    for (endpoints, &cem) in endpoints.iter_mut().zip(&cems[..partitions]) {
+        let mut v = [0; 8];
+        for value in &mut v[..(cem as usize / 4 + 1) * 2] {
+            *value = values.next().unwrap();
+        }
+        *endpoints = decode_endpoints(cem, v);
+        if !hdr && (endpoints.rgb_hdr || endpoints.alpha_hdr) {
+            return None;
+        }
+    }
+
+    let mut weight_values = [(0, 0); 64];
+    decode_ise(
+        data.reverse_bits() & ((1 << weight_bits) - 1),
+        weight_levels,
+        &mut weight_values[..weight_count],
+    );
+    let weights = weight_values.map(|value| unquantize_weight(weight_levels, value));
+
+    let small_block = width * height < 31;
+    for y in 0..height {
+        for x in 0..width {
+            let partition = if partitions > 1 {
+                select_partition(
+                    partition_seed,
+                    x as u32,
+                    y as u32,
+                    partitions as u32,
+                    small_block,
+                )
+            } else {
+                0
+            };
+            let Endpoints {
+                e0,
+                e1,
+                rgb_hdr,
+                alpha_hdr,
+            } = endpoints[partition];
+            let plane_weights: [u32; 2] = core::array::from_fn(|plane| {
+                infill_weight(
+                    &weights,
+                    plane,
+                    planes,
+                    grid_width,
+                    grid_height,
+                    width,
+                    height,
+                    x,
+                    y,
+                )
+            });
+            texels[y * width + x] = core::array::from_fn(|c| {
+                let weight = plane_weights[(c == color_component_selector) as usize] as i32;
+                let value = (e0[c] * (64 - weight) + e1[c] * weight + 32) >> 6;
+                if (c < 3 && rgb_hdr) || (c == 3 && alpha_hdr) {
+                    f16_to_f32(lns_to_f16(value as u32))
+                } else {
+                    value as f32 / 65535.0
+                }
+            });
+        }
+    }
+    Some(())
+}
+
+/// Interpolates the weight of a texel from the weight grid.
+#[expect(
+    clippy::too_many_arguments,
+    reason = "the grid and block sizes and the texel position"
+)]
+fn infill_weight(
+    weights: &[u32],
+    plane: usize,
+    planes: usize,
+    grid_width: usize,
+    grid_height: usize,
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+) -> u32 {
+    if plane >= planes {
+        return 0;
+    }
+    let grid_position = |texel: usize, size: usize, grid_size: usize| {
+        let scale = (1024 + size / 2) / (size - 1);
+        let position = (scale * texel * (grid_size - 1) + 32) >> 6;
+        (position >> 4, (position & 15) as u32)
+    };
+    let (js, fs) = grid_position(x, width, grid_width);
+    let (jt, ft) = grid_position(y, height, grid_height);
+    let weight = |s: usize, t: usize| {
+        if s < grid_width && t < grid_height {
+            weights[(t * grid_width + s) * planes + plane]
+        } else {
+            0
+        }
+    };
+    let w11 = (fs * ft + 8) >> 4;
+    let w10 = ft - w11;
+    let w01 = fs - w11;
+    let w00 = 16 - fs - ft + w11;
+    (weight(js, jt) * w00
+        + weight(js + 1, jt) * w01
+        + weight(js, jt + 1) * w10
+        + weight(js + 1, jt + 1) * w11
+        + 8)
+        >> 4
+}
+
+/// Returns the partition of a texel in a block with the given partition seed.
+fn select_partition(seed: u32, x: u32, y: u32, partitions: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block {
+        (x << 1, y << 1)
+    } else {
+        (x, y)
+    };
+    let seed = seed + (partitions - 1) * 1024;
+    let mut rnum = seed;
+    rnum ^= rnum >> 15;
+    rnum = rnum.wrapping_sub(rnum << 17);
+    rnum = rnum.wrapping_add(rnum << 7);
+    rnum = rnum.wrapping_add(rnum << 4);
+    rnum ^= rnum >> 5;
+    rnum = rnum.wrapping_add(rnum << 16);
+    rnum ^= rnum >> 7;
+    rnum ^= rnum >> 3;
+    rnum ^= rnum << 6;
+    rnum ^= rnum >> 17;
+
+    let mut seeds = [
+        rnum,
+        rnum >> 4,
+        rnum >> 8,
+        rnum >> 12,
+        rnum >> 16,
+        rnum >> 20,
+        rnum >> 24,
+        rnum >> 28,
+        rnum >> 18,
+        rnum >> 22,
+        rnum >> 26,
+        rnum.rotate_left(2),
+    ]
+    .map(|seed| {
+        let seed = seed & 0xf;
+        seed * seed
+    });
+    let (sh1, sh2) = if seed & 1 != 0 {
+        (
+            if seed & 2 != 0 { 4 } else { 5 },
+            if partitions == 3 { 6 } else { 5 },
+        )
+    } else {
+        (
+            if partitions == 3 { 6 } else { 5 },
+            if seed & 2 != 0 { 4 } else { 5 },
+        )
+    };
+    let sh3 = if seed & 0x10 != 0 { sh1 } else { sh2 };
+    for (i, seed) in seeds.iter_mut().enumerate() {
+        *seed >>= match i {
+            0..8 if i % 2 == 0 => sh1,
+            0..8 => sh2,
+            _ => sh3,
+        };
+    }
+    // The z coordinate is always 0 for 2D blocks.
+    let a = (seeds[0] * x + seeds[1] * y + (rnum >> 14)) & 0x3f;
+    let b = (seeds[2] * x + seeds[3] * y + (rnum >> 10)) & 0x3f;
+    let c = if partitions < 3 {
+        0
+    } else {
+        (seeds[4] * x + seeds[5] * y + (rnum >> 6)) & 0x3f
+    };
+    let d = if partitions < 4 {
+        0
+    } else {
+        (seeds[6] * x + seeds[7] * y + (rnum >> 2)) & 0x3f
+    };
+    if a >= b && a >= c && a >= d {
+        0
+    } else if b >= c && b >= d {
+        1
+    } else if c >= d {
+        2
+    } else {
+        3
+    }
+}
+
+/// Converts a logarithmic HDR value to the bits of a half float.
+fn lns_to_f16(value: u32) -> u16 {
+    let exponent = value >> 11;
+    let mantissa = value & 0x7ff;
+    let mantissa = if mantissa < 512 {
+        mantissa * 3
+    } else if mantissa < 1536 {
+        mantissa * 4 - 512
+    } else {
+        mantissa * 5 - 2048
+    };
+    ((exponent << 10) + (mantissa >> 3)).min(0x7bff) as u16
+}
+
+/// The alpha of HDR endpoints without alpha, 1.0 as a 12 bit logarithmic value.
+const HDR_ALPHA_ONE: i32 = 0x780;
+
+/// Decodes the color endpoints of a partition from its endpoint mode and unquantized values.
+fn decode_endpoints(cem: u32, v: [i32; 8]) -> Endpoints {
+    let ldr = |e0: [i32; 4], e1: [i32; 4]| Endpoints {
+        e0: e0.map(|c| c.clamp(0, 255) * 257),
+        e1: e1.map(|c| c.clamp(0, 255) * 257),
+        rgb_hdr: false,
+        alpha_hdr: false,
+    };
+    // 12 bit HDR values are extended to 16 bits.
+    let hdr = |e0: [i32; 4], e1: [i32; 4], alpha_hdr: bool| {
+        let extend = |e: [i32; 4]| {
+            core::array::from_fn(|c| {
+                if c < 3 || alpha_hdr {
+                    e[c] << 4
+                } else {
+                    e[c] * 257
+                }
+            })
+        };
+        Endpoints {
+            e0: extend(e0),
+            e1: extend(e1),
+            rgb_hdr: true,
+            alpha_hdr,
+        }
+    };
+    let blue_contract = |[r, g, b, a]: [i32; 4]| [(r + b) >> 1, (g + b) >> 1, b, a];
+    match cem {
+        0 => ldr([v[0], v[0], v[0], 255], [v[1], v[1], v[1], 255]),
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xc0);
+            let l1 = (l0 + (v[1] & 0x3f)).min(255);
+            ldr([l0, l0, l0, 255], [l1, l1, l1, 255])
+        }
+        2 => {
+            let (y0, y1) = if v[1] >= v[0] {
+                (v[0] << 4, v[1] << 4)
+            } else {
+                ((v[1] << 4) + 8, (v[0] << 4) - 8)
+            };
+            hdr(
+                [y0, y0, y0, HDR_ALPHA_ONE],
+                [y1, y1, y1, HDR_ALPHA_ONE],
+                true,
+            )
+        }
+        3 => {
+            let (y0, d) = if v[0] & 0x80 != 0 {
+                (
+                    ((v[1] & 0xe0) << 4) | ((v[0] & 0x7f) << 2),
+                    (v[1] & 0x1f) << 2,
+                )
+            } else {
+                (
+                    ((v[1] & 0xf0) << 4) | ((v[0] & 0x7f) << 1),
+                    (v[1] & 0x0f) << 1,
+                )
+            };
+            let y1 = (y0 + d).min(0xfff);
+            hdr(
+                [y0, y0, y0, HDR_ALPHA_ONE],
+                [y1, y1, y1, HDR_ALPHA_ONE],
+                true,
+            )
+        }
+        4 => ldr([v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]),
+        5 => {
+            let (d0, l) = bit_transfer_signed(v[1], v[0]);
+            let (d1, a) = bit_transfer_signed(v[3], v[2]);
+            ldr([l, l, l, a], [l + d0, l + d0, l + d0, a + d1])
+        }
+        6 => ldr(
+            [
+                (v[0] * v[3]) >> 8,
+                (v[1] * v[3]) >> 8,
+                (v[2] * v[3]) >> 8,
+                255,
+            ],
+            [v[0], v[1], v[2], 255],
+        ),
+        7 => {
+            let (e0, e1) = hdr_rgb_base_scale(v);
+            hdr(e0, e1, true)
+        }
+        8 | 12 => {
+            let (a0, a1) = if cem == 12 { (v[6], v[7]) } else { (255, 255) };
+            let e0 = [v[0], v[2], v[4], a0];
+            let e1 = [v[1], v[3], v[5], a1];
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                ldr(e0, e1)
+            } else {
+                ldr(blue_contract(e1), blue_contract(e0))
+            }
+        }
+        9 | 13 => {
+            let (d0, r) = bit_transfer_signed(v[1], v[0]);
+            let (d1, g) = bit_transfer_signed(v[3], v[2]);
+            let (d2, b) = bit_transfer_signed(v[5], v[4]);
+            let (d3, a) = if cem == 13 {
+                bit_transfer_signed(v[7], v[6])
+            } else {
+                (0, 255)
+            };
+            let e0 = [r, g, b, a];
+            let e1 = [r + d0, g + d1, b + d2, a + d3];
+            if d0 + d1 + d2 >= 0 {
+                ldr(e0, e1)
+            } else {
+                ldr(blue_contract(e1), blue_contract(e0))
+            }
+        }
+        10 => ldr(
+            [
+                (v[0] * v[3]) >> 8,
+                (v[1] * v[3]) >> 8,
+                (v[2] * v[3]) >> 8,
+                v[4],
+            ],
+            [v[0], v[1], v[2], v[5]],
+        ),
+        11 => {
+            let (e0, e1) = hdr_rgb_direct(v);
+            hdr(e0, e1, true)
+        }
+        14 => {
+            let (mut e0, mut e1) = hdr_rgb_direct(v);
+            e0[3] = v[6];
+            e1[3] = v[7];
+            hdr(e0, e1, false)
+        }
+        _ => {
+            let (mut e0, mut e1) = hdr_rgb_direct(v);
+            (e0[3], e1[3]) = hdr_alpha(v[6], v[7]);
+            hdr(e0, e1, true)
+        }
+    }
+}
+
+/// Moves the top bit of `a` to `b` and returns `a` as a signed 6 bit delta.
+fn bit_transfer_signed(a: i32, b: i32) -> (i32, i32) {
+    let b = (b >> 1) | (a & 0x80);
+    let a = (a >> 1) & 0x3f;
+    let a = if a & 0x20 != 0 { a - 0x40 } else { a };
+    (a, b)
+}
+
+/// Decodes the 12 bit endpoints of the HDR RGB base and scale mode.
+fn hdr_rgb_base_scale(v: [i32; 8]) -> ([i32; 4], [i32; 4]) {
+    let mode_value = ((v[0] & 0xc0) >> 6) | ((v[1] & 0x80) >> 5) | ((v[2] & 0x80) >> 4);
+    let (major_component, mode) = if mode_value & 0xc != 0xc {
+        (mode_value >> 2, mode_value & 3)
+    } else if mode_value != 0xf {
+        (mode_value & 3, 4)
+    } else {
+        (0, 5)
+    };
+    let mut red = v[0] & 0x3f;
+    let mut green = v[1] & 0x1f;
+    let mut blue = v[2] & 0x1f;
+    let mut scale = v[3] & 0x1f;
+    let bit = |value: i32, i: i32| (value >> i) & 1;
+    let [bit0, bit1, bit2, bit3, bit4, bit5, bit6] = [
+        bit(v[1], 6),
+        bit(v[1], 5),
+        bit(v[2], 6),
+        bit(v[2], 5),
+        bit(v[3], 7),
+        bit(v[3], 6),
+        bit(v[3], 5),
+    ];
+    let one_hot = 1 << mode;
+    if one_hot & 0x30 != 0 {
+        green |= bit0 << 6;
+        blue |= bit2 << 6;
+    }
+    if one_hot & 0x3a != 0 {
+        green |= bit1 << 5;
+        blue |= bit3 << 5;
+    }
+    if one_hot & 0x3d != 0 {
+        scale |= bit6 << 5;
+    }
+    if one_hot & 0x2d != 0 {
+        scale |= bit5 << 6;
+    }
+    if one_hot & 0x04 != 0 {
+        scale |= bit4 << 7;
+    }
+    if one_hot & 0x3b != 0 {
+        red |= bit4 << 6;
+    }
+    if one_hot & 0x04 != 0 {
+        red |= bit3 << 6;
+    }
+    if one_hot & 0x10 != 0 {
+        red |= bit5 << 7;
+    }
+    if one_hot & 0x0f != 0 {
+        red |= bit2 << 7;
+    }
+    if one_hot & 0x05 != 0 {
+        red |= bit1 << 8;
+    }
+    if one_hot & 0x0a != 0 {
+        red |= bit0 << 8;
+    }
+    if one_hot & 0x05 != 0 {
+        red |= bit0 << 9;
+    }
+    if one_hot & 0x02 != 0 {
+        red |= bit6 << 9;
+    }
+    if one_hot & 0x01 != 0 {
+        red |= bit3 << 10;
+    }
+    if one_hot & 0x02 != 0 {
+        red |= bit5 << 10;
+    }
+    let shift = [1, 1, 2, 3, 4, 5][mode as usize];
+    red <<= shift;
+    green <<= shift;
+    blue <<= shift;
+    scale <<= shift;
+    if mode != 5 {
+        green = red - green;
+        blue = red - blue;
+    }
+    let mut e1 = [red, green, blue];
+    match major_component {
+        1 => e1.swap(0, 1),
+        2 => e1.swap(0, 2),
+        _ => {}
+    }
+    let [r, g, b] = e1.map(|c| c.clamp(0, 0xfff));
+    let [r0, g0, b0] = e1.map(|c| (c - scale).clamp(0, 0xfff));
+    ([r0, g0, b0, HDR_ALPHA_ONE], [r, g, b, HDR_ALPHA_ONE])
+}
+
+/// Decodes the 12 bit endpoints of the HDR RGB direct mode, also used by the HDR RGBA modes.
+fn hdr_rgb_direct(v: [i32; 8]) -> ([i32; 4], [i32; 4]) {
+    let mode_value = ((v[1] & 0x80) >> 7) | ((v[2] & 0x80) >> 6) | ((v[3] & 0x80) >> 5);
+    let major_component = ((v[4] & 0x80) >> 7) | ((v[5] & 0x80) >> 6);
+    if major_component == 3 {
+        return (
+            [v[0] << 4, v[2] << 4, (v[4] & 0x7f) << 5, HDR_ALPHA_ONE],
+            [v[1] << 4, v[3] << 4, (v[5] & 0x7f) << 5, HDR_ALPHA_ONE],
+        );
+    }
+    let mut a = v[0] | ((v[1] & 0x40) << 2);
+    let mut b0 = v[2] & 0x3f;
+    let mut b1 = v[3] & 0x3f;
+    let mut c = v[1] & 0x3f;
+    let mut d0 = v[4] & 0x7f;
+    let mut d1 = v[5] & 0x7f;
+    let d_bits = [7, 6, 7, 6, 5, 6, 5, 6][mode_value as usize];
+    let bit = |value: i32, i: i32| (value >> i) & 1;
+    let [bit0, bit1, bit2, bit3, bit4, bit5] = [
+        bit(v[2], 6),
+        bit(v[3], 6),
+        bit(v[4], 6),
+        bit(v[5], 6),
+        bit(v[4], 5),
+        bit(v[5], 5),
+    ];
+    let one_hot = 1 << mode_value;
+    if one_hot & 0xa4 != 0 {
+        a |= bit0 << 9;
+    }
+    if one_hot & 0x8 != 0 {
+        a |= bit2 << 9;
+    }
+    if one_hot & 0x50 != 0 {
+        a |= bit4 << 9;
+        a |= bit5 << 10;
+    }
+    if one_hot & 0xa0 != 0 {
+        a |= bit1 << 10;
+    }
+    if one_hot & 0xc0 != 0 {
+        a |= bit2 << 11;
+    }
+    if one_hot & 0x4 != 0 {
+        c |= bit1 << 6;
+    }
+    if one_hot & 0xe8 != 0 {
+        c |= bit3 << 6;
+    }
+    if one_hot & 0x20 != 0 {
+        c |= bit2 << 7;
+    }
+    if one_hot & 0x5b != 0 {
+        b0 |= bit0 << 6;
+        b1 |= bit1 << 6;
+    }
+    if one_hot & 0x12 != 0 {
+        b0 |= bit2 << 7;
+        b1 |= bit3 << 7;
+    }
+    if one_hot & 0xaf != 0 {
+        d0 |= bit4 << 5;
+        d1 |= bit5 << 5;
+    }
+    if one_hot & 0x5 != 0 {
+        d0 |= bit2 << 6;
+        d1 |= bit3 << 6;
+    }
+    // Sign extend the deltas.
+    let d0 = (d0 << (32 - d_bits)) >> (32 - d_bits);
+    let d1 = (d1 << (32 - d_bits)) >> (32 - d_bits);
+    let shift = (mode_value >> 1) ^ 3;
+    let [a, b0, b1, c, d0, d1] = [a, b0, b1, c, d0, d1].map(|value| value << shift);
+    let mut e0 = [a - c, a - b0 - c - d0, a - b1 - c - d1];
+    let mut e1 = [a, a - b0, a - b1];
+    match major_component {
+        1 => {
+            e0.swap(0, 1);
+            e1.swap(0, 1);
+        }
+        2 => {
+            e0.swap(0, 2);
+            e1.swap(0, 2);
+        }
+        _ => {}
+    }
+    let [r0, g0, b0] = e0.map(|c| c.clamp(0, 0xfff));
+    let [r1, g1, b1] = e1.map(|c| c.clamp(0, 0xfff));
+    ([r0, g0, b0, HDR_ALPHA_ONE], [r1, g1, b1, HDR_ALPHA_ONE])
+}
+
+/// Decodes the 12 bit alpha endpoints of the HDR RGBA mode.
+fn hdr_alpha(v6: i32, v7: i32) -> (i32, i32) {
+    let selector = ((v6 >> 7) & 1) | ((v7 >> 6) & 2);
+    let (mut v6, mut v7) = (v6 & 0x7f, v7 & 0x7f);
+    if selector == 3 {
+        return (v6 << 5, v7 << 5);
+    }
+    v6 |= (v7 << (selector + 1)) & 0x780;
+    v7 &= 0x3f >> selector;
+    v7 ^= 32 >> selector;
+    v7 -= 32 >> selector;
+    v6 <<= 4 - selector;
+    v7 <<= 4 - selector;
+    (v6, (v6 + v7).clamp(0, 0xfff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn void_extent() {
+        let mut block = [0u8; 16];
+        block[..2].copy_from_slice(&0x1fcu16.to_le_bytes());
+        for (c, value) in [0xffffu16, 0x8000, 0, 0xffff].into_iter().enumerate() {
+            block[8 + c * 2..][..2].copy_from_slice(&value.to_le_bytes());
+        }
+        let mut pixels = [[0; 4]; 16];
+        decode_ldr_4x4(&block, &mut pixels);
+        assert!(pixels.iter().all(|pixel| *pixel == [255, 128, 0, 255]));
+
+        // HDR void-extent blocks are errors in the LDR profile.
+        block[1] |= 0x2;
+        decode_ldr_4x4(&block, &mut pixels);
+        assert_eq!(pixels[0], [255, 0, 255, 255]);
+        for (c, value) in [0x3c00u16, 0x3800, 0, 0x3c00].into_iter().enumerate() {
+            block[8 + c * 2..][..2].copy_from_slice(&value.to_le_bytes());
+        }
+        let mut pixels = [[0.0; 4]; 16];
+        decode_hdr_4x4(&block, &mut pixels);
+        assert_eq!(pixels[0], [1.0, 0.5, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn trits_and_quints() {
+        // Every trit and quint combination is encoded by some bits.
+        let mut trits = std::collections::HashSet::new();
+        for t in 0..256 {
+            trits.insert(decode_trits(t));
+        }
+        assert_eq!(trits.len(), 243);
+        let mut quints = std::collections::HashSet::new();
+        for q in 0..128 {
+            quints.insert(decode_quints(q));
+        }
+        assert_eq!(quints.len(), 125);
+    }
+
+    #[test]
+    fn unquantize() {
+        let weights = |levels: u32| {
+            let (trits, quints, bits) = range_encoding(levels);
+            let base = if trits {
+                3
+            } else if quints {
+                5
+            } else {
+                1
+            };
+            let mut weights = (0..base)
+                .flat_map(|d| (0..1 << bits).map(move |m| unquantize_weight(levels, (d, m))))
+                .collect::<Vec<_>>();
+            weights.sort();
+            weights
+        };
+        assert_eq!(weights(3), [0, 32, 64]);
+        assert_eq!(weights(6), [0, 12, 25, 39, 52, 64]);
+        assert_eq!(weights(2), [0, 64]);
+        for levels in RANGES[..12].iter().copied() {
+            let weights = weights(levels);
+            assert_eq!(weights.len(), levels as usize);
+            assert_eq!((weights[0], weights[weights.len() - 1]), (0, 64));
+        }
+        for levels in RANGES[4..].iter().copied() {
+            let (trits, quints, bits) = range_encoding(levels);
+            let base = if trits {
+                3
+            } else if quints {
+                5
+            } else {
+                1
+            };
+            let mut colors = (0..base)
+                .flat_map(|d| (0..1 << bits).map(move |m| unquantize_color(levels, (d, m))))
+                .collect::<Vec<_>>();
+            colors.sort();
+            colors.dedup();
+            assert_eq!(colors.len(), levels as usize);
+            assert_eq!((colors[0], colors[colors.len() - 1]), (0, 255));
+        }
+    }
+
+    #[test]
+    fn partitions() {
+        // Each partition count selects all of its partitions for some seed and texel.
+        for partitions in 2..=4 {
+            let mut selected = [false; 4];
+            for seed in 0..1024 {
+                for texel in 0..16 {
+                    selected[select_partition(seed, texel % 4, texel / 4, partitions, true)] = true;
+                }
+            }
+            assert_eq!(
+                selected.iter().filter(|&&selected| selected).count(),
+                partitions as usize
+            );
+        }
+    }
+
+    #[test]
+    fn ldr_luminance_block() {
+        // Block mode: 4x4 weight grid with 2 bit weights, range 4 (R=4 → bits 4..0 = 0b00100 → R0=0 and R2R1 = 10).
+        // Bits 1..0 = R2 R1 = 0b10, bits 3..2 = 0 (B+4 x A+2), bit 4 = R0 = 0, A = 2 (bits 6..5), B = 0 (bits 8..7).
+        let mode = 0b10 | 2 << 5;
+        let block_mode = BlockMode::decode(mode).unwrap();
+        assert_eq!((block_mode.grid_width, block_mode.grid_height), (4, 4));
+        assert_eq!(block_mode.weight_levels, 4);
+
+        // One partition with CEM 0 (luminance direct), endpoints 0 and 255 with 8 bit values.
+        let mut data = mode as u128;
+        data |= 0 << 13;
+        data |= 0xff00 << 17;
+        // The weights of the top row are 0, 1, 2 and 3, stored bit reversed from the end of the block.
+        let weights = [0u128, 1, 2, 3];
+        for (i, weight) in weights.into_iter().enumerate() {
+            let reversed = (weight & 1) << 1 | weight >> 1;
+            data |= reversed << (126 - 2 * i);
+        }
+        let mut pixels = [[0; 4]; 16];
+        decode_ldr_4x4(&data.to_le_bytes(), &mut pixels);
+        assert_eq!(pixels[0], [0, 0, 0, 255]);
+        assert_eq!(pixels[1], [84, 84, 84, 255]);
+        assert_eq!(pixels[2], [171, 171, 171, 255]);
+        assert_eq!(pixels[3], [255, 255, 255, 255]);
+        assert_eq!(pixels[4], [0, 0, 0, 255]);
+    }
+}
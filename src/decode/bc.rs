@@ -0,0 +1,660 @@
+//! Decoders of the BC formats, see the Khronos Data Format Specification.
+
+use super::{BitReader, f16_to_f32};
+
+pub(super) fn decode_bc1(block: &[u8], pixels: &mut [[u8; 4]]) {
+    decode_bc1_color(block, pixels, true);
+}
+
+pub(super) fn decode_bc3(block: &[u8], pixels: &mut [[u8; 4]]) {
+    decode_bc1_color(&block[8..], pixels, false);
+    let alpha = bc4_values(&block[..8]);
+    for (pixel, alpha) in pixels.iter_mut().zip(alpha) {
+        pixel[3] = alpha;
+    }
+}
+
+pub(super) fn decode_bc4(block: &[u8], pixels: &mut [[u8; 4]]) {
+    for (pixel, red) in pixels.iter_mut().zip(bc4_values(block)) {
+        *pixel = [red, 0, 0, 255];
+    }
+}
+
+pub(super) fn decode_bc5(block: &[u8], pixels: &mut [[u8; 4]]) {
+    let red = bc4_values(&block[..8]);
+    let green = bc4_values(&block[8..]);
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = [red[i], green[i], 0, 255];
+    }
+}
+
+/// Decodes the color block of BC1, or BC3 which always uses 4 colors.
+fn decode_bc1_color(block: &[u8], pixels: &mut [[u8; 4]], punch_through: bool) {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let [c0, c1] = [color0, color1].map(|color| {
+        [
+            expand_bits((color >> 11) as u32, 5),
+            expand_bits((color >> 5 & 0x3f) as u32, 6),
+            expand_bits((color & 0x1f) as u32, 5),
+        ]
+    });
+    let mix = |w0: u32, w1: u32| -> [u8; 4] {
+        let channel = |c: usize| ((w0 * c0[c] as u32 + w1 * c1[c] as u32) / (w0 + w1)) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+    let palette = if color0 > color1 || !punch_through {
+        [mix(1, 0), mix(0, 1), mix(2, 1), mix(1, 2)]
+    } else {
+        [mix(1, 0), mix(0, 1), mix(1, 1), [0; 4]]
+    };
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[(indices >> (i * 2) & 3) as usize];
+    }
+}
+
+/// Decodes the 16 values of a BC4 block, also used for the alpha of BC3 and the channels of BC5.
+fn bc4_values(block: &[u8]) -> [u8; 16] {
+    let (v0, v1) = (block[0] as u32, block[1] as u32);
+    let palette: [u8; 8] = if v0 > v1 {
+        core::array::from_fn(|i| match i {
+            0 => v0 as u8,
+            1 => v1 as u8,
+            _ => (((8 - i as u32) * v0 + (i as u32 - 1) * v1) / 7) as u8,
+        })
+    } else {
+        core::array::from_fn(|i| match i {
+            0 => v0 as u8,
+            1 => v1 as u8,
+            6 => 0,
+            7 => 255,
+            _ => (((6 - i as u32) * v0 + (i as u32 - 1) * v1) / 5) as u8,
+        })
+    };
+    let mut indices = [0; 8];
+    indices[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(indices);
+    core::array::from_fn(|i| palette[(indices >> (i * 3) & 7) as usize])
+}
+
+/// Expands an unsigned normalized value of `bits` bits to 8 bits.
+fn expand_bits(value: u32, bits: u32) -> u8 {
+    let value = value << (8 - bits);
+    (value | value >> bits) as u8
+}
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn weight(index: u32, bits: u32) -> u32 {
+    match bits {
+        2 => WEIGHTS_2[index as usize],
+        3 => WEIGHTS_3[index as usize],
+        _ => WEIGHTS_4[index as usize],
+    }
+}
+
+fn interpolate(e0: u32, e1: u32, weight: u32) -> u32 {
+    ((64 - weight) * e0 + weight * e1 + 32) >> 6
+}
+
+/// The subset of each pixel of the BC6H and BC7 partitions with 2 subsets, one bit per pixel.
+const PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80, 0xc800, 0xffec, 0xfe80, 0xe800,
+    0xffe8, 0xff00, 0xfff0, 0xf000, 0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c, 0xaaaa, 0xf0f0, 0x5a5a, 0x33cc,
+    0x3c3c, 0x55aa, 0x9696, 0xa55a, 0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c, 0x9336, 0x9cc6, 0x817e, 0xe718,
+    0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// The subset of each pixel of the BC7 partitions with 3 subsets, two bits per pixel.
+const PARTITIONS_3: [u32; 64] = [
+    0xaa685050, 0x6a5a5040, 0x5a5a4200, 0x5450a0a8, 0xa5a50000, 0xa0a05050, 0x5555a0a0, 0x5a5a5050,
+    0xaa550000, 0xaa555500, 0xaaaa5500, 0x90909090, 0x94949494, 0xa4a4a4a4, 0xa9a59450, 0x2a0a4250,
+    0xa5945040, 0x0a425054, 0xa5a5a500, 0x55a0a0a0, 0xa8a85454, 0x6a6a4040, 0xa4a45000, 0x1a1a0500,
+    0x0050a4a4, 0xaaa59090, 0x14696914, 0x69691400, 0xa08585a0, 0xaa821414, 0x50a4a450, 0x6a5a0200,
+    0xa9a58000, 0x5090a0a8, 0xa8a09050, 0x24242424, 0x00aa5500, 0x24924924, 0x24499224, 0x50a50a50,
+    0x500aa550, 0xaaaa4444, 0x66660000, 0xa5a0a5a0, 0x50a050a0, 0x69286928, 0x44aaaa44, 0x66666600,
+    0xaa444444, 0x54a854a8, 0x95809580, 0x96969600, 0xa85454a8, 0x80959580, 0xaa141414, 0x96960000,
+    0xaaaa1414, 0xa05050a0, 0xa0a5a5a0, 0x96000000, 0x40804080, 0xa9a8a9a8, 0xaaaaaa44, 0x2a4a5254,
+];
+
+/// The anchor pixel of the second subset of the partitions with 2 subsets.
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// The anchor pixels of the second and third subset of the partitions with 3 subsets.
+const ANCHORS_3: [[u8; 2]; 64] = [
+    [3, 15],
+    [3, 8],
+    [15, 8],
+    [15, 3],
+    [8, 15],
+    [3, 15],
+    [15, 3],
+    [15, 8],
+    [8, 15],
+    [8, 15],
+    [6, 15],
+    [6, 15],
+    [6, 15],
+    [5, 15],
+    [3, 15],
+    [3, 8],
+    [3, 15],
+    [3, 8],
+    [8, 15],
+    [15, 3],
+    [3, 15],
+    [3, 8],
+    [6, 15],
+    [10, 8],
+    [5, 3],
+    [8, 15],
+    [8, 6],
+    [6, 10],
+    [8, 15],
+    [5, 15],
+    [15, 10],
+    [15, 8],
+    [8, 15],
+    [15, 3],
+    [3, 15],
+    [5, 10],
+    [6, 10],
+    [10, 8],
+    [8, 9],
+    [15, 10],
+    [15, 6],
+    [3, 15],
+    [15, 8],
+    [5, 15],
+    [15, 3],
+    [15, 6],
+    [15, 6],
+    [15, 8],
+    [3, 15],
+    [15, 3],
+    [5, 15],
+    [5, 15],
+    [5, 15],
+    [8, 15],
+    [5, 15],
+    [10, 15],
+    [5, 15],
+    [10, 15],
+    [8, 15],
+    [13, 15],
+    [15, 3],
+    [12, 15],
+    [3, 15],
+    [3, 8],
+];
+
+fn subset(subsets: usize, partition: usize, pixel: usize) -> usize {
+    match subsets {
+        2 => (PARTITIONS_2[partition] >> pixel & 1) as usize,
+        3 => (PARTITIONS_3[partition] >> (pixel * 2) & 3) as usize,
+        _ => 0,
+    }
+}
+
+fn is_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+    pixel == 0
+        || match subsets {
+            2 => ANCHORS_2[partition] as usize == pixel,
+            3 => ANCHORS_3[partition].contains(&(pixel as u8)),
+            _ => false,
+        }
+}
+
+/// The layout of a BC7 mode.
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index2_bits: u32,
+}
+
+const fn bc7_mode(
+    subsets: usize,
+    [
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+    ]: [u32; 5],
+    [endpoint_pbits, shared_pbits]: [bool; 2],
+    [index_bits, index2_bits]: [u32; 2],
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_pbits,
+        shared_pbits,
+        index_bits,
+        index2_bits,
+    }
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    bc7_mode(3, [4, 0, 0, 4, 0], [true, false], [3, 0]),
+    bc7_mode(2, [6, 0, 0, 6, 0], [false, true], [3, 0]),
+    bc7_mode(3, [6, 0, 0, 5, 0], [false, false], [2, 0]),
+    bc7_mode(2, [6, 0, 0, 7, 0], [true, false], [2, 0]),
+    bc7_mode(1, [0, 2, 1, 5, 6], [false, false], [2, 3]),
+    bc7_mode(1, [0, 2, 0, 7, 8], [false, false], [2, 2]),
+    bc7_mode(1, [0, 0, 0, 7, 7], [true, false], [4, 0]),
+    bc7_mode(2, [6, 0, 0, 5, 5], [true, false], [2, 0]),
+];
+
+pub(super) fn decode_bc7(block: &[u8], pixels: &mut [[u8; 4]]) {
+    // Blocks without a mode bit are reserved and decode to transparent black.
+    if block[0] == 0 {
+        pixels.fill([0; 4]);
+        return;
+    }
+    let mode_index = block[0].trailing_zeros();
+    let mode = &BC7_MODES[mode_index as usize];
+    let mut reader = BitReader::new(block);
+    reader.read(mode_index + 1);
+    let partition = reader.read(mode.partition_bits) as usize;
+    let rotation = reader.read(mode.rotation_bits);
+    let index_selection = reader.read(mode.index_selection_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..4 {
+        let bits = if channel < 3 {
+            mode.color_bits
+        } else {
+            mode.alpha_bits
+        };
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = reader.read(bits);
+        }
+    }
+    let mut pbits = [0; 6];
+    if mode.endpoint_pbits {
+        for pbit in &mut pbits[..endpoint_count] {
+            *pbit = reader.read(1);
+        }
+    } else if mode.shared_pbits {
+        for subset in 0..mode.subsets {
+            let pbit = reader.read(1);
+            pbits[subset * 2] = pbit;
+            pbits[subset * 2 + 1] = pbit;
+        }
+    }
+    let has_pbits = mode.endpoint_pbits || mode.shared_pbits;
+    for (endpoint, pbit) in endpoints[..endpoint_count].iter_mut().zip(pbits) {
+        for (channel, value) in endpoint.iter_mut().enumerate() {
+            let bits = if channel < 3 {
+                mode.color_bits
+            } else {
+                mode.alpha_bits
+            };
+            *value = if bits == 0 {
+                255
+            } else if has_pbits {
+                expand_bits(*value << 1 | pbit, bits + 1) as u32
+            } else {
+                expand_bits(*value, bits) as u32
+            };
+        }
+    }
+
+    let mut indices = [0; 16];
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.subsets, partition, pixel);
+        *index = reader.read(mode.index_bits - anchor as u32);
+    }
+    let mut indices2 = [0; 16];
+    if mode.index2_bits > 0 {
+        for (pixel, index) in indices2.iter_mut().enumerate() {
+            *index = reader.read(mode.index2_bits - (pixel == 0) as u32);
+        }
+    }
+
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let subset = subset(mode.subsets, partition, i);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        let (color_weight, alpha_weight) = if mode.index2_bits == 0 {
+            let weight = weight(indices[i], mode.index_bits);
+            (weight, weight)
+        } else if index_selection == 0 {
+            (
+                weight(indices[i], mode.index_bits),
+                weight(indices2[i], mode.index2_bits),
+            )
+        } else {
+            (
+                weight(indices2[i], mode.index2_bits),
+                weight(indices[i], mode.index_bits),
+            )
+        };
+        *pixel = core::array::from_fn(|channel| {
+            let weight = if channel < 3 {
+                color_weight
+            } else {
+                alpha_weight
+            };
+            interpolate(e0[channel], e1[channel], weight) as u8
+        });
+        if rotation > 0 {
+            pixel.swap(3, rotation as usize - 1);
+        }
+    }
+}
+
+// The endpoint fields of BC6H, endpoint `w`, `x`, `y` or `z` and channel.
+const RW: u8 = 0;
+const GW: u8 = 1;
+const BW: u8 = 2;
+const RX: u8 = 3;
+const GX: u8 = 4;
+const BX: u8 = 5;
+const RY: u8 = 6;
+const GY: u8 = 7;
+const BY: u8 = 8;
+const RZ: u8 = 9;
+const GZ: u8 = 10;
+const BZ: u8 = 11;
+
+/// The layout of a BC6H mode.
+struct Bc6hMode {
+    /// The mode bits, 2 or 5.
+    mode: u32,
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    /// The endpoint bits after the mode bits in storage order, as the field and its first and last bit.
+    ///
+    /// The bits of some fields are stored in reverse, from the most significant bit.
+    fields: &'static [(u8, u8, u8)],
+}
+
+impl Bc6hMode {
+    fn regions(&self) -> usize {
+        if self.mode & 3 == 3 { 1 } else { 2 }
+    }
+}
+
+#[rustfmt::skip]
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode { mode: 0b00, transformed: true, endpoint_bits: 10, delta_bits: [5, 5, 5], fields: &[
+        (GY, 4, 4), (BY, 4, 4), (BZ, 4, 4), (RW, 0, 9), (GW, 0, 9), (BW, 0, 9), (RX, 0, 4), (GZ, 4, 4),
+        (GY, 0, 3), (GX, 0, 4), (BZ, 0, 0), (GZ, 0, 3), (BX, 0, 4), (BZ, 1, 1), (BY, 0, 3), (RY, 0, 4),
+        (BZ, 2, 2), (RZ, 0, 4), (BZ, 3, 3),
+    ] },
+    Bc6hMode { mode: 0b01, transformed: true, endpoint_bits: 7, delta_bits: [6, 6, 6], fields: &[
+        (GY, 5, 5), (GZ, 4, 4), (GZ, 5, 5), (RW, 0, 6), (BZ, 0, 0), (BZ, 1, 1), (BY, 4, 4), (GW, 0, 6),
+        (BY, 5, 5), (BZ, 2, 2), (GY, 4, 4), (BW, 0, 6), (BZ, 3, 3), (BZ, 5, 5), (BZ, 4, 4), (RX, 0, 5),
+        (GY, 0, 3), (GX, 0, 5), (GZ, 0, 3), (BX, 0, 5), (BY, 0, 3), (RY, 0, 5), (RZ, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b00010, transformed: true, endpoint_bits: 11, delta_bits: [5, 4, 4], fields: &[
+        (RW, 0, 9), (GW, 0, 9), (BW, 0, 9), (RX, 0, 4), (RW, 10, 10), (GY, 0, 3), (GX, 0, 3),
+        (GW, 10, 10), (BZ, 0, 0), (GZ, 0, 3), (BX, 0, 3), (BW, 10, 10), (BZ, 1, 1), (BY, 0, 3),
+        (RY, 0, 4), (BZ, 2, 2), (RZ, 0, 4), (BZ, 3, 3),
+    ] },
+    Bc6hMode { mode: 0b00110, transformed: true, endpoint_bits: 11, delta_bits: [4, 5, 4], fields: &[
+        (RW, 0, 9), (GW, 0, 9), (BW, 0, 9), (RX, 0, 3), (RW, 10, 10), (GZ, 4, 4), (GY, 0, 3),
+        (GX, 0, 4), (GW, 10, 10), (GZ, 0, 3), (BX, 0, 3), (BW, 10, 10), (BZ, 1, 1), (BY, 0, 3),
+        (RY, 0, 3), (BZ, 0, 0), (BZ, 2, 2), (RZ, 0, 3), (GY, 4, 4), (BZ, 3, 3),
+    ] },
+    Bc6hMode { mode: 0b01010, transformed: true, endpoint_bits: 11, delta_bits: [4, 4, 5], fields: &[
+        (RW, 0, 9), (GW, 0, 9), (BW, 0, 9), (RX, 0, 3), (RW, 10, 10), (BY, 4, 4), (GY, 0, 3),
+        (GX, 0, 3), (GW, 10, 10), (BZ, 0, 0), (GZ, 0, 3), (BX, 0, 4), (BW, 10, 10), (BY, 0, 3),
+        (RY, 0, 3), (BZ, 1, 1), (BZ, 2, 2), (RZ, 0, 3), (BZ, 4, 4), (BZ, 3, 3),
+    ] },
+    Bc6hMode { mode: 0b01110, transformed: true, endpoint_bits: 9, delta_bits: [5, 5, 5], fields: &[
+        (RW, 0, 8), (BY, 4, 4), (GW, 0, 8), (GY, 4, 4), (BW, 0, 8), (BZ, 4, 4), (RX, 0, 4), (GZ, 4, 4),
+        (GY, 0, 3), (GX, 0, 4), (BZ, 0, 0), (GZ, 0, 3), (BX, 0, 4), (BZ, 1, 1), (BY, 0, 3), (RY, 0, 4),
+        (BZ, 2, 2), (RZ, 0, 4), (BZ, 3, 3),
+    ] },
+    Bc6hMode { mode: 0b10010, transformed: true, endpoint_bits: 8, delta_bits: [6, 5, 5], fields: &[
+        (RW, 0, 7), (GZ, 4, 4), (BY, 4, 4), (GW, 0, 7), (BZ, 2, 2), (GY, 4, 4), (BW, 0, 7), (BZ, 3, 3),
+        (BZ, 4, 4), (RX, 0, 5), (GY, 0, 3), (GX, 0, 4), (BZ, 0, 0), (GZ, 0, 3), (BX, 0, 4), (BZ, 1, 1),
+        (BY, 0, 3), (RY, 0, 5), (RZ, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b10110, transformed: true, endpoint_bits: 8, delta_bits: [5, 6, 5], fields: &[
+        (RW, 0, 7), (BZ, 0, 0), (BY, 4, 4), (GW, 0, 7), (GY, 5, 5), (GY, 4, 4), (BW, 0, 7), (GZ, 5, 5),
+        (BZ, 4, 4), (RX, 0, 4), (GZ, 4, 4), (GY, 0, 3), (GX, 0, 5), (GZ, 0, 3), (BX, 0, 4), (BZ, 1, 1),
+        (BY, 0, 3), (RY, 0, 4), (BZ, 2, 2), (RZ, 0, 4), (BZ, 3, 3),
+    ] },
+    Bc6hMode { mode: 0b11010, transformed: true, endpoint_bits: 8, delta_bits: [5, 5, 6], fields: &[
+        (RW, 0, 7), (BZ, 1, 1), (BY, 4, 4), (GW, 0, 7), (BY, 5, 5), (GY, 4, 4), (BW, 0, 7), (BZ, 5, 5),
+        (BZ, 4, 4), (RX, 0, 4), (GZ, 4, 4), (GY, 0, 3), (GX, 0, 4), (BZ, 0, 0), (GZ, 0, 3), (BX, 0, 5),
+        (BY, 0, 3), (RY, 0, 4), (BZ, 2, 2), (RZ, 0, 4), (BZ, 3, 3),
+    ] },
+    Bc6hMode { mode: 0b11110, transformed: false, endpoint_bits: 6, delta_bits: [6, 6, 6], fields: &[
+        (RW, 0, 5), (GZ, 4, 4), (BZ, 0, 0), (BZ, 1, 1), (BY, 4, 4), (GW, 0, 5), (GY, 5, 5), (BY, 5, 5),
+        (BZ, 2, 2), (GY, 4, 4), (BW, 0, 5), (GZ, 5, 5), (BZ, 3, 3), (BZ, 5, 5), (BZ, 4, 4), (RX, 0, 5),
+        (GY, 0, 3), (GX, 0, 5), (GZ, 0, 3), (BX, 0, 5), (BY, 0, 3), (RY, 0, 5), (RZ, 0, 5),
+    ] },
+    Bc6hMode { mode: 0b00011, transformed: false, endpoint_bits: 10, delta_bits: [10, 10, 10], fields: &[
+        (RW, 0, 9), (GW, 0, 9), (BW, 0, 9), (RX, 0, 9), (GX, 0, 9), (BX, 0, 9),
+    ] },
+    Bc6hMode { mode: 0b00111, transformed: true, endpoint_bits: 11, delta_bits: [9, 9, 9], fields: &[
+        (RW, 0, 9), (GW, 0, 9), (BW, 0, 9), (RX, 0, 8), (RW, 10, 10), (GX, 0, 8), (GW, 10, 10),
+        (BX, 0, 8), (BW, 10, 10),
+    ] },
+    Bc6hMode { mode: 0b01011, transformed: true, endpoint_bits: 12, delta_bits: [8, 8, 8], fields: &[
+        (RW, 0, 9), (GW, 0, 9), (BW, 0, 9), (RX, 0, 7), (RW, 11, 10), (GX, 0, 7), (GW, 11, 10),
+        (BX, 0, 7), (BW, 11, 10),
+    ] },
+    Bc6hMode { mode: 0b01111, transformed: true, endpoint_bits: 16, delta_bits: [4, 4, 4], fields: &[
+        (RW, 0, 9), (GW, 0, 9), (BW, 0, 9), (RX, 0, 3), (RW, 15, 10), (GX, 0, 3), (GW, 15, 10),
+        (BX, 0, 3), (BW, 15, 10),
+    ] },
+];
+
+pub(super) fn decode_bc6h(block: &[u8], pixels: &mut [[f32; 4]]) {
+    let mut reader = BitReader::new(block);
+    let mut mode_bits = reader.read(2);
+    if mode_bits > 1 {
+        mode_bits |= reader.read(3) << 2;
+    }
+    // Reserved modes decode to black.
+    let Some(mode) = BC6H_MODES.iter().find(|mode| mode.mode == mode_bits) else {
+        pixels.fill([0.0, 0.0, 0.0, 1.0]);
+        return;
+    };
+
+    let mut endpoints = [[0u32; 3]; 4];
+    for &(field, first, last) in mode.fields {
+        let value = &mut endpoints[field as usize / 3][field as usize % 3];
+        if first <= last {
+            *value |= reader.read((last - first + 1) as u32) << first;
+        } else {
+            for bit in (last..=first).rev() {
+                *value |= reader.read(1) << bit;
+            }
+        }
+    }
+    let regions = mode.regions();
+    let partition = if regions == 2 {
+        reader.read(5) as usize
+    } else {
+        0
+    };
+    let endpoint_mask = (1 << mode.endpoint_bits) - 1;
+    if mode.transformed {
+        let base = endpoints[0];
+        for endpoint in &mut endpoints[1..regions * 2] {
+            for channel in 0..3 {
+                let bits = mode.delta_bits[channel];
+                // Sign extend the delta.
+                let delta = ((endpoint[channel] << (32 - bits)) as i32 >> (32 - bits)) as u32;
+                endpoint[channel] = base[channel].wrapping_add(delta) & endpoint_mask;
+            }
+        }
+    }
+    let endpoints = endpoints.map(|endpoint| {
+        endpoint.map(|value| {
+            if mode.endpoint_bits >= 15 {
+                value
+            } else if value == 0 {
+                0
+            } else if value == endpoint_mask {
+                0xffff
+            } else {
+                ((value << 16) + 0x8000) >> mode.endpoint_bits
+            }
+        })
+    });
+
+    let index_bits = if regions == 2 { 3 } else { 4 };
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let anchor = is_anchor(regions, partition, i);
+        let weight = weight(reader.read(index_bits - anchor as u32), index_bits);
+        let region = subset(regions, partition, i);
+        let (e0, e1) = (endpoints[region * 2], endpoints[region * 2 + 1]);
+        let [r, g, b] = core::array::from_fn(|channel| {
+            let value = interpolate(e0[channel], e1[channel], weight);
+            f16_to_f32(((value * 31) >> 6) as u16)
+        });
+        *pixel = [r, g, b, 1.0];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partition_anchors() {
+        for partition in 0..64 {
+            assert_eq!(subset(2, partition, 0), 0);
+            assert_eq!(subset(2, partition, ANCHORS_2[partition] as usize), 1);
+            assert_eq!(subset(3, partition, 0), 0);
+            for (subset_index, anchor) in ANCHORS_3[partition].into_iter().enumerate() {
+                assert_eq!(subset(3, partition, anchor as usize), subset_index + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn bc6h_mode_fields() {
+        for mode in &BC6H_MODES {
+            let mode_bits = if mode.mode > 1 { 5 } else { 2 };
+            let field_bits: u32 = mode
+                .fields
+                .iter()
+                .map(|&(_, first, last)| first.abs_diff(last) as u32 + 1)
+                .sum();
+            let header_bits = if mode.regions() == 2 { 77 } else { 65 };
+            assert_eq!(mode_bits + field_bits, header_bits, "mode {:#b}", mode.mode);
+
+            for field in 0..mode.regions() as u8 * 6 {
+                let width = if field < 3 {
+                    mode.endpoint_bits
+                } else if mode.transformed {
+                    mode.delta_bits[field as usize % 3]
+                } else {
+                    mode.endpoint_bits
+                };
+                let mut covered = 0u32;
+                for &(_, first, last) in mode.fields.iter().filter(|(f, ..)| *f == field) {
+                    for bit in first.min(last)..=first.max(last) {
+                        assert_eq!(covered & 1 << bit, 0, "mode {:#b}", mode.mode);
+                        covered |= 1 << bit;
+                    }
+                }
+                assert_eq!(
+                    covered,
+                    (1 << width) - 1,
+                    "mode {:#b} field {field}",
+                    mode.mode
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bc1() {
+        // Red and blue endpoints, the pixels use index 0, 1, 2 and 3 in turn.
+        let block = [0x00, 0xf8, 0x1f, 0x00, 0xe4, 0xe4, 0xe4, 0xe4];
+        let mut pixels = [[0; 4]; 16];
+        decode_bc1(&block, &mut pixels);
+        assert_eq!(pixels[0], [255, 0, 0, 255]);
+        assert_eq!(pixels[1], [0, 0, 255, 255]);
+        assert_eq!(pixels[2], [170, 0, 85, 255]);
+        assert_eq!(pixels[3], [85, 0, 170, 255]);
+    }
+
+    #[test]
+    fn bc4() {
+        let block = [200, 100, 0b1000_1000, 0, 0, 0, 0, 0];
+        let mut pixels = [[0; 4]; 16];
+        decode_bc4(&block, &mut pixels);
+        assert_eq!(pixels[0], [200, 0, 0, 255]);
+        assert_eq!(pixels[1], [100, 0, 0, 255]);
+        assert_eq!(pixels[2], [185, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc7_mode6() {
+        // Mode 6 with RGBA endpoints (127, 0, 0, 127) and (0, 127, 0, 127), the p-bits 1 and all indices the largest.
+        // With the p-bits, the second endpoint is (1, 255, 1, 255).
+        let mut bits = 1u128 << 6;
+        let mut offset = 7;
+        let mut write = |value: u128, count: u32| {
+            bits |= value << offset;
+            offset += count;
+        };
+        for value in [127, 0, 0, 127, 0, 0, 127, 127] {
+            write(value, 7);
+        }
+        write(1, 1);
+        write(1, 1);
+        write(0b111, 3);
+        for _ in 1..16 {
+            write(0b1111, 4);
+        }
+        let mut pixels = [[0; 4]; 16];
+        decode_bc7(&bits.to_le_bytes(), &mut pixels);
+        // The anchor index has an implicit most significant bit of 0, so the index of the first pixel is 7.
+        assert_eq!(pixels[0], [136, 120, 1, 255]);
+        assert!(pixels[1..].iter().all(|pixel| *pixel == [1, 255, 1, 255]));
+    }
+
+    #[test]
+    fn bc6h_mode11() {
+        // Mode 11 with the endpoints 0 and 1023 (1.0 as half is 0x3c00), all indices 15.
+        let mut bits = 0b00011u128;
+        let mut offset = 5;
+        let mut write = |value: u128, count: u32| {
+            bits |= value << offset;
+            offset += count;
+        };
+        for value in [0, 0, 0, (0x3c00 * 64 / 31) >> 6, 0, 0] {
+            write(value, 10);
+        }
+        write(0b111, 3);
+        for _ in 1..16 {
+            write(0b1111, 4);
+        }
+        let mut pixels = [[0.0; 4]; 16];
+        decode_bc6h(&bits.to_le_bytes(), &mut pixels);
+        assert!((pixels[5][0] - 1.0).abs() < 0.01, "{:?}", pixels[5]);
+        assert_eq!(pixels[5][1..], [0.0, 0.0, 1.0]);
+    }
+}
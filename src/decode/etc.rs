@@ -0,0 +1,227 @@
+//! Decoders of the ETC2 and EAC formats, see the Khronos Data Format Specification.
+
+/// The intensity modifiers of the ETC1 modes, the large ones are used by the indices 1 and 3.
+const ETC1_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// The distances of the T and H modes.
+const ETC2_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+pub(super) fn decode_etc2_rgb(block: &[u8], pixels: &mut [[u8; 4]]) {
+    let bits = u64::from_be_bytes(block.try_into().unwrap());
+    let field = |offset: u32, count: u32| (bits >> offset & ((1 << count) - 1)) as i32;
+    // The pixel indices are stored in column-major order, the most significant bits first.
+    let index = |x: usize, y: usize| {
+        let bit = (x * 4 + y) as u32;
+        (field(bit + 16, 1) << 1 | field(bit, 1)) as usize
+    };
+    let extend = |value: i32, bits: u32| (value << (8 - bits) | value >> (2 * bits - 8)) as u8;
+    let signed = |value: i32| value << 29 >> 29;
+
+    if field(33, 1) == 0 {
+        // Individual mode, two 4 bit base colors.
+        let base0 = [field(60, 4), field(52, 4), field(44, 4)].map(|c| extend(c, 4));
+        let base1 = [field(56, 4), field(48, 4), field(40, 4)].map(|c| extend(c, 4));
+        decode_etc1_subblocks(base0, base1, field, index, pixels);
+        return;
+    }
+    let base = [field(59, 5), field(51, 5), field(43, 5)];
+    let delta = [field(56, 3), field(48, 3), field(40, 3)].map(signed);
+    let overflow = |c: usize| !(0..32).contains(&(base[c] + delta[c]));
+    let mut paint = |colors: [[u8; 3]; 4]| {
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let [r, g, b] = colors[index(i % 4, i / 4)];
+            *pixel = [r, g, b, 255];
+        }
+    };
+    let shift =
+        |color: [u8; 3], distance: i32| color.map(|c| (c as i32 + distance).clamp(0, 255) as u8);
+
+    if overflow(0) {
+        // T mode.
+        let color0 =
+            [field(59, 2) << 2 | field(56, 2), field(52, 4), field(48, 4)].map(|c| extend(c, 4));
+        let color1 = [field(44, 4), field(40, 4), field(36, 4)].map(|c| extend(c, 4));
+        let distance = ETC2_DISTANCES[(field(34, 2) << 1 | field(32, 1)) as usize];
+        paint([
+            color0,
+            shift(color1, distance),
+            color1,
+            shift(color1, -distance),
+        ]);
+    } else if overflow(1) {
+        // H mode.
+        let color0 = [
+            field(59, 4),
+            field(56, 3) << 1 | field(52, 1),
+            field(51, 1) << 3 | field(47, 3),
+        ]
+        .map(|c| extend(c, 4));
+        let color1 = [field(43, 4), field(39, 4), field(35, 4)].map(|c| extend(c, 4));
+        let value = |[r, g, b]: [u8; 3]| (r as u32) << 16 | (g as u32) << 8 | b as u32;
+        let distance_index =
+            field(34, 1) << 2 | field(32, 1) << 1 | (value(color0) >= value(color1)) as i32;
+        let distance = ETC2_DISTANCES[distance_index as usize];
+        paint([
+            shift(color0, distance),
+            shift(color0, -distance),
+            shift(color1, distance),
+            shift(color1, -distance),
+        ]);
+    } else if overflow(2) {
+        // Planar mode, the colors are interpolated between the origin, horizontal and vertical colors.
+        let origin = [
+            extend(field(57, 6), 6),
+            extend(field(56, 1) << 6 | field(49, 6), 7),
+            extend(field(48, 1) << 5 | field(43, 2) << 3 | field(39, 3), 6),
+        ];
+        let horizontal = [
+            extend(field(34, 5) << 1 | field(32, 1), 6),
+            extend(field(25, 7), 7),
+            extend(field(19, 6), 6),
+        ];
+        let vertical = [
+            extend(field(13, 6), 6),
+            extend(field(6, 7), 7),
+            extend(field(0, 6), 6),
+        ];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let (x, y) = ((i % 4) as i32, (i / 4) as i32);
+            let [r, g, b] = core::array::from_fn(|c| {
+                let (o, h, v) = (origin[c] as i32, horizontal[c] as i32, vertical[c] as i32);
+                ((x * (h - o) + y * (v - o) + 4 * o + 2) >> 2).clamp(0, 255) as u8
+            });
+            *pixel = [r, g, b, 255];
+        }
+    } else {
+        // Differential mode, the second base color is relative to the first.
+        let base0 = base.map(|c| extend(c, 5));
+        let base1 = core::array::from_fn(|c| extend(base[c] + delta[c], 5));
+        decode_etc1_subblocks(base0, base1, field, index, pixels);
+    }
+}
+
+/// Decodes the two subblocks of the ETC1 individual and differential modes.
+fn decode_etc1_subblocks(
+    base0: [u8; 3],
+    base1: [u8; 3],
+    field: impl Fn(u32, u32) -> i32,
+    index: impl Fn(usize, usize) -> usize,
+    pixels: &mut [[u8; 4]],
+) {
+    let tables = [field(37, 3), field(34, 3)];
+    let flipped = field(32, 1) == 1;
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let (x, y) = (i % 4, i / 4);
+        let subblock = if flipped { y >= 2 } else { x >= 2 } as usize;
+        let base = [base0, base1][subblock];
+        let index = index(x, y);
+        let modifier = ETC1_MODIFIERS[tables[subblock] as usize][index & 1];
+        let modifier = if index & 2 == 0 { modifier } else { -modifier };
+        let [r, g, b] = base.map(|c| (c as i32 + modifier).clamp(0, 255) as u8);
+        *pixel = [r, g, b, 255];
+    }
+}
+
+pub(super) fn decode_etc2_rgba(block: &[u8], pixels: &mut [[u8; 4]]) {
+    decode_etc2_rgb(&block[8..], pixels);
+    for (pixel, alpha) in pixels.iter_mut().zip(eac_values(&block[..8], false)) {
+        pixel[3] = alpha as u8;
+    }
+}
+
+pub(super) fn decode_eac_r11(block: &[u8], pixels: &mut [[u8; 4]]) {
+    for (pixel, red) in pixels.iter_mut().zip(eac_values(block, true)) {
+        *pixel = [unorm11_to_unorm8(red), 0, 0, 255];
+    }
+}
+
+pub(super) fn decode_eac_rg11(block: &[u8], pixels: &mut [[u8; 4]]) {
+    let red = eac_values(&block[..8], true);
+    let green = eac_values(&block[8..], true);
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = [
+            unorm11_to_unorm8(red[i]),
+            unorm11_to_unorm8(green[i]),
+            0,
+            255,
+        ];
+    }
+}
+
+fn unorm11_to_unorm8(value: u32) -> u8 {
+    ((value * 255 + 1023) / 2047) as u8
+}
+
+/// Decodes the 16 values of an EAC block in row-major order, 8 bit alpha or 11 bit unsigned values if `r11`.
+fn eac_values(block: &[u8], r11: bool) -> [u32; 16] {
+    let bits = u64::from_be_bytes(block.try_into().unwrap());
+    let base = (bits >> 56) as i32;
+    let multiplier = (bits >> 52 & 0xf) as i32;
+    let modifiers = EAC_MODIFIERS[(bits >> 48 & 0xf) as usize];
+    core::array::from_fn(|i| {
+        // The indices are stored in column-major order.
+        let (x, y) = (i % 4, i / 4);
+        let modifier = modifiers[(bits >> (45 - 3 * (x * 4 + y)) & 7) as usize];
+        if r11 {
+            let multiplier = if multiplier == 0 { 1 } else { multiplier * 8 };
+            (base * 8 + 4 + modifier * multiplier).clamp(0, 2047) as u32
+        } else {
+            (base + modifier * multiplier).clamp(0, 255) as u32
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etc1_individual() {
+        // Base colors 0x8 and 0x4 per channel, tables 0 and 7, pixel (0, 0) has index 1 and pixel (3, 0) index 2.
+        let block = [0x84, 0x84, 0x84, 0b0001_1100, 0x10, 0x00, 0x00, 0x01];
+        let mut pixels = [[0; 4]; 16];
+        decode_etc2_rgb(&block, &mut pixels);
+        assert_eq!(pixels[0], [136 + 8, 136 + 8, 136 + 8, 255]);
+        assert_eq!(pixels[1], [138, 138, 138, 255]);
+        assert_eq!(pixels[3], [68 - 47, 68 - 47, 68 - 47, 255]);
+    }
+
+    #[test]
+    fn eac() {
+        // Base 128, multiplier 2, table 0, pixel (1, 0) has index 7.
+        let block = [128, 0x20, 0b0000_0000, 0b0000_1110, 0, 0, 0, 0];
+        let alpha = eac_values(&block, false);
+        assert_eq!(alpha[0], 128 - 6);
+        assert_eq!(alpha[1], 128 + 28);
+        let red = eac_values(&block, true);
+        assert_eq!(red[0], 128 * 8 + 4 - 3 * 16);
+    }
+}
@@ -0,0 +1,226 @@
+use core::time::Duration;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender};
+
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticMeasurement, DiagnosticPath, DiagnosticsStore, RegisterDiagnostic,
+};
+use bevy::platform::collections::HashMap;
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+
+/// Registers diagnostics of the textures transcoded by [`BasisuLoader`](crate::BasisuLoader), e.g. for `LogDiagnosticsPlugin`.
+///
+/// The totals since startup are measured every frame. The transcode time and GPU bytes of each target format are registered
+/// the first time a texture is transcoded to it, see [`Self::transcode_time`] and [`Self::gpu_bytes`].
+/// Textures loaded from a [`TranscodeCache`](crate::TranscodeCache) count as [`Self::CACHE_HITS`] and towards the output and GPU bytes.
+/// Progressive previews aren't counted.
+#[derive(Default, Debug, Clone)]
+pub struct BasisuDiagnosticsPlugin;
+
+impl Plugin for BasisuDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        // Totals are running counts, an average or smoothing would be meaningless.
+        let total = |path| {
+            Diagnostic::new(path)
+                .with_smoothing_factor(0.0)
+                .with_max_history_length(0)
+        };
+        app.init_resource::<TranscodeStatsChannel>()
+            .register_diagnostic(total(Self::TEXTURES_TRANSCODED))
+            .register_diagnostic(total(Self::INPUT_BYTES).with_suffix(" bytes"))
+            .register_diagnostic(total(Self::OUTPUT_BYTES).with_suffix(" bytes"))
+            .register_diagnostic(total(Self::CACHE_HITS))
+            .add_systems(Update, record_transcode_stats);
+    }
+}
+
+impl BasisuDiagnosticsPlugin {
+    /// Total textures transcoded since startup.
+    pub const TEXTURES_TRANSCODED: DiagnosticPath =
+        DiagnosticPath::const_new("basisu/textures_transcoded");
+
+    /// Total bytes of the transcoded KTX2 and `.basis` files.
+    pub const INPUT_BYTES: DiagnosticPath = DiagnosticPath::const_new("basisu/input_bytes");
+
+    /// Total bytes of the transcoded and cached textures.
+    pub const OUTPUT_BYTES: DiagnosticPath = DiagnosticPath::const_new("basisu/output_bytes");
+
+    /// Total textures loaded from a [`TranscodeCache`](crate::TranscodeCache) since startup.
+    pub const CACHE_HITS: DiagnosticPath = DiagnosticPath::const_new("basisu/cache_hits");
+
+    /// The time in ms to transcode a texture to `format`, measured once per texture.
+    pub fn transcode_time(format: TextureFormat) -> DiagnosticPath {
+        DiagnosticPath::new(format!("basisu/transcode_time/{format:?}"))
+    }
+
+    /// Total bytes of the textures transcoded or cached in `format`.
+    pub fn gpu_bytes(format: TextureFormat) -> DiagnosticPath {
+        DiagnosticPath::new(format!("basisu/gpu_bytes/{format:?}"))
+    }
+}
+
+/// A texture transcoded by the loader.
+pub(crate) struct TranscodeStats {
+    pub(crate) format: TextureFormat,
+    pub(crate) input_bytes: usize,
+    pub(crate) output_bytes: usize,
+    /// The transcode time, or `None` if the texture was loaded from a [`TranscodeCache`](crate::TranscodeCache).
+    pub(crate) duration: Option<Duration>,
+}
+
+/// Sends [`TranscodeStats`] from the loader tasks to [`record_transcode_stats`] and keeps the totals.
+#[derive(Resource)]
+pub(crate) struct TranscodeStatsChannel {
+    pub(crate) sender: Sender<TranscodeStats>,
+    receiver: Mutex<Receiver<TranscodeStats>>,
+    textures: u64,
+    cache_hits: u64,
+    input_bytes: u64,
+    output_bytes: u64,
+    gpu_bytes: HashMap<TextureFormat, u64>,
+}
+
+impl Default for TranscodeStatsChannel {
+    fn default() -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        Self {
+            sender,
+            receiver: Mutex::new(receiver),
+            textures: 0,
+            cache_hits: 0,
+            input_bytes: 0,
+            output_bytes: 0,
+            gpu_bytes: HashMap::default(),
+        }
+    }
+}
+
+fn record_transcode_stats(
+    mut channel: ResMut<TranscodeStatsChannel>,
+    mut diagnostics: ResMut<DiagnosticsStore>,
+) {
+    let channel = &mut *channel;
+    let now = Instant::now();
+    let mut add_measurement = |path: DiagnosticPath, value: f64, suffix: &'static str| {
+        if diagnostics.get(&path).is_none() {
+            diagnostics.add(Diagnostic::new(path.clone()).with_suffix(suffix));
+        }
+        diagnostics
+            .get_mut(&path)
+            .unwrap()
+            .add_measurement(DiagnosticMeasurement { time: now, value });
+    };
+    for stats in channel.receiver.get_mut().unwrap().try_iter() {
+        channel.output_bytes += stats.output_bytes as u64;
+        *channel.gpu_bytes.entry(stats.format).or_default() += stats.output_bytes as u64;
+        let Some(duration) = stats.duration else {
+            channel.cache_hits += 1;
+            continue;
+        };
+        channel.textures += 1;
+        channel.input_bytes += stats.input_bytes as u64;
+        add_measurement(
+            BasisuDiagnosticsPlugin::transcode_time(stats.format),
+            duration.as_secs_f64() * 1000.0,
+            "ms",
+        );
+    }
+    for (&format, &bytes) in &channel.gpu_bytes {
+        add_measurement(
+            BasisuDiagnosticsPlugin::gpu_bytes(format),
+            bytes as f64,
+            " bytes",
+        );
+    }
+    add_measurement(
+        BasisuDiagnosticsPlugin::TEXTURES_TRANSCODED,
+        channel.textures as f64,
+        "",
+    );
+    add_measurement(
+        BasisuDiagnosticsPlugin::CACHE_HITS,
+        channel.cache_hits as f64,
+        "",
+    );
+    add_measurement(
+        BasisuDiagnosticsPlugin::INPUT_BYTES,
+        channel.input_bytes as f64,
+        " bytes",
+    );
+    add_measurement(
+        BasisuDiagnosticsPlugin::OUTPUT_BYTES,
+        channel.output_bytes as f64,
+        " bytes",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_transcode_stats() {
+        let mut app = App::new();
+        app.add_plugins(BasisuDiagnosticsPlugin);
+        let sender = app
+            .world()
+            .resource::<TranscodeStatsChannel>()
+            .sender
+            .clone();
+        for (format, output_bytes, duration) in [
+            (
+                TextureFormat::Bc7RgbaUnormSrgb,
+                100,
+                Some(Duration::from_millis(2)),
+            ),
+            (
+                TextureFormat::Bc7RgbaUnormSrgb,
+                50,
+                Some(Duration::from_millis(2)),
+            ),
+            (
+                TextureFormat::Rgba8Unorm,
+                400,
+                Some(Duration::from_millis(2)),
+            ),
+            (TextureFormat::Rgba8Unorm, 400, None),
+        ] {
+            sender
+                .send(TranscodeStats {
+                    format,
+                    input_bytes: 10,
+                    output_bytes,
+                    duration,
+                })
+                .unwrap();
+        }
+        app.update();
+
+        let diagnostics = app.world().resource::<DiagnosticsStore>();
+        let value = |path| diagnostics.get(&path).unwrap().value().unwrap();
+        assert_eq!(value(BasisuDiagnosticsPlugin::TEXTURES_TRANSCODED), 3.0);
+        assert_eq!(value(BasisuDiagnosticsPlugin::CACHE_HITS), 1.0);
+        assert_eq!(value(BasisuDiagnosticsPlugin::INPUT_BYTES), 30.0);
+        assert_eq!(value(BasisuDiagnosticsPlugin::OUTPUT_BYTES), 950.0);
+        assert_eq!(
+            value(BasisuDiagnosticsPlugin::gpu_bytes(
+                TextureFormat::Bc7RgbaUnormSrgb
+            )),
+            150.0
+        );
+        assert_eq!(
+            value(BasisuDiagnosticsPlugin::gpu_bytes(
+                TextureFormat::Rgba8Unorm
+            )),
+            800.0
+        );
+        assert_eq!(
+            value(BasisuDiagnosticsPlugin::transcode_time(
+                TextureFormat::Rgba8Unorm
+            )),
+            2.0
+        );
+    }
+}
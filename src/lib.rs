@@ -1,33 +1,112 @@
 use bevy::prelude::*;
 use bevy::render::{RenderApp, renderer::RenderDevice};
 
+mod animation;
+mod cache;
+mod decode;
+mod diagnostics;
 mod loader;
+mod metadata;
+mod padding;
+#[cfg(feature = "processor")]
+mod processor;
+mod progressive;
+mod target;
+mod transform;
 
+pub use animation::*;
+pub use cache::*;
+pub use decode::*;
+pub use diagnostics::*;
 pub use loader::*;
+pub use metadata::*;
+pub use padding::*;
+#[cfg(feature = "processor")]
+pub use processor::*;
+pub use target::*;
 
 /// Provides a loader for Basis Universal KTX2 textures.
 ///
-/// The file extension must be `.basisu_ktx2` to use this loader. Supports KTX2 UASTC/ETC1S format. Zstd supercompression is supported even if bevy's zstd feature is disabled. No support for `.basis` files.
+/// The file extension must be `.basisu_ktx2` to use this loader, or `.ktx2` with the `ktx2` feature enabled, in which case KTX2 files that aren't Basis Universal encoded are loaded by Bevy's `ImageLoader`. Supports KTX2 UASTC/ETC1S format. Zstd supercompression is supported even if bevy's zstd feature is disabled.
+///
+/// The key/value data of KTX2 textures is loaded as the [`Ktx2Metadata`] sub-asset labeled [`KTX2_METADATA_LABEL`].
+///
+/// Animated KTX2 textures (flipbooks and ETC1S video with `KTXanimData`) are loaded as their first frame and a [`BasisuAnimation`] sub-asset labeled [`BASISU_ANIMATION_LABEL`],
+/// which is played into an [`Image`] by a [`BasisuAnimationPlayer`].
+///
+/// With [`BasisuLoaderSettings::decoded_copy`], an uncompressed copy of the texture that stays in the main world is added as the [`Image`] sub-asset labeled [`BASISU_DECODED_LABEL`].
+///
+/// With the `processor` feature, `BasisuProcessor` is registered to pre-transcode `.basisu_ktx2` and `.basis` files to the formats of a `TranscodeProfile`. It isn't the default processor of any extension, select it per asset in `.meta` files or with `set_default_asset_processor`.
+///
+/// The compressed formats the loader transcodes to can be decoded back to RGBA8 or RGBA32F on the CPU by [`decode_to_rgba`] and [`decode_image_to_rgba`], e.g. for tests without a GPU.
+///
+/// Add [`BasisuDiagnosticsPlugin`] to measure the number, bytes and transcode time of the transcoded textures with `bevy_diagnostic`.
+///
+/// With [`Self::transcode_cache`], transcoded KTX2 textures are written to a [`TranscodeCache`] directory and loaded from it on later launches without transcoding.
+///
+/// Textures whose size isn't a multiple of the block size of the transcode target are handled according to [`UnalignedSizePolicy`], by default they are transcoded to an uncompressed format.
+///
+/// Legacy `.basis` files are supported with the `.basis` extension. They produce the same [`Image`] layout as KTX2: 2D array and video textures become `D2Array`, cubemap arrays become `Cube` or `CubeArray`. All images in a `.basis` file must have the same dimensions and mip level count.
 ///
 /// Transcode Target Selection:
 /// - ETC1S: Bc7Rgba/Bc5Rg/Bc4R > Etc2Rgba8/Etc2Rgb8/EacRg11/EacR11 > Rgba8
 /// - UASTC LDR: Astc > Bc7Rgba > Etc2Rgba8/Etc2Rgb8/EacRg11/EacR11 > Rgba8
 /// - UASTC HDR: Astc > Bc6hRgbUfloat > Rgba16Float
-pub struct BasisuLoaderPlugin;
+///
+/// Targets that the device doesn't support are skipped. The priority can be changed per basis format and channel type by [`Self::target_priority`]
+/// and [`BasisuLoaderSettings::target_priority`].
+#[derive(Default, Debug, Clone)]
+pub struct BasisuLoaderPlugin {
+    /// The maximum number of tasks used to transcode a single KTX2 texture on native platforms.
+    ///
+    /// Levels, layers and faces are transcoded in parallel on the [`AsyncComputeTaskPool`](bevy::tasks::AsyncComputeTaskPool).
+    /// If `None`, as many tasks as the pool has threads are used. Set it to 1 to transcode on a single thread.
+    pub max_transcode_tasks: Option<core::num::NonZeroUsize>,
+    /// The transcode targets to try first, e.g. to prefer [`TranscodeTarget::Astc`] over [`TranscodeTarget::Etc2`] for ETC1S textures on mobile.
+    ///
+    /// The first rule matching the basis format and channel type of a texture applies, after the rules of [`BasisuLoaderSettings::target_priority`].
+    pub target_priority: Vec<TargetPriorityRule>,
+    /// A persistent cache of transcoded KTX2 textures, so that they are only transcoded on the first launch. Ignored on web.
+    ///
+    /// E.g. `Some(TranscodeCache::new("cache/basisu"))`. Animated textures and `.basis` files aren't cached.
+    pub transcode_cache: Option<TranscodeCache>,
+    /// The number of Web Workers that transcode KTX2 textures on web, each with its own Basis Universal instance. Ignored on native platforms.
+    ///
+    /// With 0 (the default) textures are transcoded on the main thread, which blocks the page while a large texture is transcoded.
+    /// The slices of a texture are split among at most [`Self::max_transcode_tasks`] workers.
+    pub web_workers: usize,
+}
 
 impl Plugin for BasisuLoaderPlugin {
     fn build(&self, app: &mut App) {
-        app.preregister_asset_loader::<BasisuLoader>(&["basisu_ktx2"])
-            .add_systems(PreStartup, || {
+        #[cfg(all(
+            target_arch = "wasm32",
+            target_vendor = "unknown",
+            target_os = "unknown",
+        ))]
+        let web_workers = self.web_workers;
+        app.preregister_asset_loader::<BasisuLoader>(loader::EXTENSIONS)
+            .init_asset::<Ktx2Metadata>()
+            .init_asset::<BasisuAnimation>()
+            .init_asset::<BasisuPadding>()
+            .init_resource::<progressive::ProgressiveImages>()
+            .add_systems(PreUpdate, progressive::replace_progressive_images)
+            .add_systems(Update, animation::play_basisu_animations)
+            .add_systems(PreStartup, move || {
                 #[cfg(all(
                     target_arch = "wasm32",
                     target_vendor = "unknown",
                     target_os = "unknown",
                 ))]
                 bevy::tasks::IoTaskPool::get()
-                    .spawn_local(async {
+                    .spawn_local(async move {
                         bevy_basisu_loader_sys::basisu_sys_init_vendor().await;
                         unsafe { bevy_basisu_loader_sys::basisu_transcoder_init() };
+                        // Without workers textures are transcoded on the main thread.
+                        let workers = bevy_basisu_loader_sys::basisu_sys_init_workers(web_workers);
+                        if let Err(err) = workers.await {
+                            bevy::log::warn!("Failed to start the BasisU Web Workers: {err:?}");
+                        }
                     })
                     .detach();
                 #[cfg(not(all(
@@ -39,6 +118,9 @@ impl Plugin for BasisuLoaderPlugin {
                     bevy_basisu_loader_sys::basisu_transcoder_init()
                 };
             });
+        // Not the default processor of any extension, processing has to be opted into.
+        #[cfg(feature = "processor")]
+        app.register_asset_processor(BasisuProcessor::default());
     }
 
     fn finish(&self, app: &mut App) {
@@ -47,6 +129,27 @@ impl Plugin for BasisuLoaderPlugin {
             .world()
             .resource::<RenderDevice>();
         let features = device.features();
-        app.register_asset_loader(BasisuLoader::from_features(features));
+        let mut loader = BasisuLoader::from_features(features);
+        loader.max_transcode_tasks = self.max_transcode_tasks;
+        loader.target_priority = self.target_priority.clone();
+        #[cfg(not(all(
+            target_arch = "wasm32",
+            target_vendor = "unknown",
+            target_os = "unknown",
+        )))]
+        {
+            loader.transcode_cache = self.transcode_cache.clone();
+        }
+        loader.stats_sender = app
+            .world()
+            .get_resource::<diagnostics::TranscodeStatsChannel>()
+            .map(|channel| channel.sender.clone());
+        loader.progressive_sender = Some(
+            app.world()
+                .resource::<progressive::ProgressiveImages>()
+                .sender
+                .clone(),
+        );
+        app.register_asset_loader(loader);
     }
 }
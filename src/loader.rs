@@ -1,17 +1,65 @@
-use bevy::asset::{AssetLoader, RenderAssetUsages};
+use bevy::asset::{AssetLoader, AssetPath, RenderAssetUsages};
 use bevy::image::ImageSampler;
+#[cfg(feature = "ktx2")]
+use bevy::image::{
+    CompressedImageFormats, ImageFormat, ImageFormatSetting, ImageLoader, ImageLoaderError,
+    ImageLoaderSettings,
+};
+use bevy::log::tracing::Instrument;
+use bevy::platform::time::Instant;
 use bevy::prelude::*;
 use bevy::render::render_resource::{
     AstcBlock, AstcChannel, Extent3d, TextureDataOrder, TextureDescriptor, TextureDimension,
     TextureFormat, TextureUsages, TextureViewDescriptor, TextureViewDimension,
     WgpuFeatures as Features,
 };
-use bevy_basisu_loader_sys::{TextureCompressionMethod, TextureTranscodedFormat};
+use bevy::tasks::AsyncComputeTaskPool;
+use bevy_basisu_loader_sys::{
+    BasisTexFormat, DecodeFlags, Ktx2Transcoder, TextureCompressionMethod, TextureLayout,
+    TextureTranscodedFormat, TranscodeError, TranscodeErrorCode, TranscodeParams, TranscodeState,
+};
+use core::num::NonZeroUsize;
+use core::ops::Range;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
 use thiserror::Error;
 
+use crate::animation::{AnimData, BASISU_ANIMATION_LABEL, BasisuAnimation};
+use crate::cache::TranscodeCache;
+use crate::diagnostics::TranscodeStats;
+use crate::metadata::{KTX2_METADATA_LABEL, Ktx2Metadata};
+use crate::padding::{BASISU_PADDING_LABEL, BasisuPadding, UnalignedSizePolicy};
+#[cfg(feature = "processor")]
+use crate::processor::BASISU_ORIGINAL_KEY;
+use crate::progressive::{ProgressiveImage, ProgressiveSender};
+use crate::target::{TargetPriorityRule, resolve_target_priority};
+use crate::transform::Ktx2Transform;
+
+/// The file extensions claimed by [`BasisuLoader`].
+///
+/// With the `ktx2` feature, `.ktx2` files are claimed as well. Basis Universal payloads are transcoded by this loader and other KTX2 files,
+/// e.g. `.basisu_ktx2` files pre-transcoded by [`BasisuProcessor`](crate::BasisuProcessor), are delegated to Bevy's [`ImageLoader`].
+/// Pre-transcoded files whose format the device doesn't support are transcoded from the original texture embedded by the processor.
+#[cfg(not(feature = "ktx2"))]
+pub(crate) const EXTENSIONS: &[&str] = &["basisu_ktx2", "basis"];
+#[cfg(feature = "ktx2")]
+pub(crate) const EXTENSIONS: &[&str] = &["basisu_ktx2", "basis", "ktx2"];
+
+/// The label of the uncompressed [`Image`] sub-asset added with [`BasisuLoaderSettings::decoded_copy`].
+///
+/// E.g. `asset_server.load::<Image>("texture.basisu_ktx2#decoded")`.
+pub const BASISU_DECODED_LABEL: &str = "decoded";
+
 pub struct BasisuLoader {
     supported_compressed_formats: TextureCompressionMethod,
+    pub(crate) target_priority: Vec<TargetPriorityRule>,
+    pub(crate) max_transcode_tasks: Option<NonZeroUsize>,
+    pub(crate) progressive_sender: Option<ProgressiveSender>,
+    pub(crate) transcode_cache: Option<TranscodeCache>,
+    pub(crate) stats_sender: Option<Sender<TranscodeStats>>,
+    #[cfg(feature = "ktx2")]
+    image_loader: ImageLoader,
 }
 
 impl BasisuLoader {
@@ -31,11 +79,596 @@ impl BasisuLoader {
         }
         Self {
             supported_compressed_formats,
+            target_priority: Vec::new(),
+            max_transcode_tasks: None,
+            progressive_sender: None,
+            transcode_cache: None,
+            stats_sender: None,
+            #[cfg(feature = "ktx2")]
+            image_loader: ImageLoader::new(CompressedImageFormats::from_features(features)),
+        }
+    }
+
+    /// Converts the loader settings to the parameters of the transcoder.
+    fn transcode_params(
+        &self,
+        settings: &BasisuLoaderSettings,
+    ) -> Result<TranscodeParams, BasisuLoaderError> {
+        // Targets compiled out of the transcoder are never selected, forcing them is an error.
+        // Queried at load time because the web transcoder is initialized after the plugin.
+        let supported_compressed_formats = self.supported_compressed_formats
+            & bevy_basisu_loader_sys::compiled_compression_methods();
+        check_force_transcode_target(settings, supported_compressed_formats)?;
+        Ok(TranscodeParams {
+            supported_compressed_formats,
+            channel_type_hint: bevy_basisu_loader_sys::ChannelType(
+                settings.channel_type_hint as u8,
+            ),
+            force_transcode_target: texture_bevy_format_to_transcode_format(
+                settings.force_transcode_target,
+            ),
+            skip_levels: settings.skip_mip_levels,
+            max_size: settings.max_resolution.map_or(0, |size| size.max(1)),
+            decode_flags: settings.decode_options.decode_flags(),
+            target_priority: resolve_target_priority(
+                settings.target_priority.iter().chain(&self.target_priority),
+            ),
+        })
+    }
+
+    /// Returns the format a KTX2 texture would be transcoded to with these settings, without transcoding it.
+    ///
+    /// [`BasisuLoaderSettings::unaligned_size`] is applied, so a texture whose size isn't a multiple of the block size of the target gets the uncompressed fallback
+    /// or a [`BasisuLoaderError::UnalignedSize`] error. The transcoder must be initialized, which [`BasisuLoaderPlugin`](crate::BasisuLoaderPlugin) does at startup.
+    pub fn transcode_target(
+        &self,
+        data: &[u8],
+        settings: &BasisuLoaderSettings,
+    ) -> Result<TextureFormat, BasisuLoaderError> {
+        let animation =
+            ktx2_key_value(data, b"KTXanimData").is_some() && !settings.load_animation_as_array;
+        let data: Arc<[u8]> = data.into();
+        let mut transcoder = Ktx2Transcoder::new()?;
+        let params = self.transcode_params(settings)?;
+        let layout = prepare_ktx2(&mut transcoder, data.clone(), &params, animation)?;
+        let info = TranscodedInfo::new(&layout, settings);
+        let Some(fallback) = unaligned_size_fallback(&layout, &info, settings)? else {
+            return Ok(info.format);
+        };
+        let params = self.transcode_params(&fallback)?;
+        let layout = prepare_ktx2(&mut transcoder, data, &params, animation)?;
+        Ok(TranscodedInfo::new(&layout, &fallback).format)
+    }
+
+    /// Transcodes a KTX2 or `.basis` texture to a single [`Image`] without sub-assets, for [`BasisuProcessor`](crate::BasisuProcessor).
+    ///
+    /// Animated KTX2 textures are transcoded as a `D2Array` of all frames. [`UnalignedSizePolicy::Pad`] isn't applied, the texture keeps its size.
+    #[cfg(feature = "processor")]
+    pub(crate) async fn transcode_image(
+        &self,
+        data: Vec<u8>,
+        settings: &BasisuLoaderSettings,
+        path: &AssetPath<'_>,
+    ) -> Result<Image, BasisuLoaderError> {
+        let mut transcoder = Ktx2Transcoder::new()?;
+        if !data.starts_with(&KTX2_IDENTIFIER) {
+            let texture = transcoder.transcode_basis(&data, &self.transcode_params(settings)?)?;
+            let info = TranscodedInfo::new(&texture.layout, settings);
+            let Some(fallback) = unaligned_size_fallback(&texture.layout, &info, settings)? else {
+                return Ok(info.to_image(texture.data, settings));
+            };
+            let texture = transcoder.transcode_basis(&data, &self.transcode_params(&fallback)?)?;
+            return Ok(
+                TranscodedInfo::new(&texture.layout, &fallback).to_image(texture.data, &fallback)
+            );
+        }
+
+        // The frames of ETC1S video depend on the previous frame, so they are transcoded in order.
+        let max_transcode_tasks = if ktx2_key_value(&data, b"KTXanimData").is_some() {
+            NonZeroUsize::new(1)
+        } else {
+            self.max_transcode_tasks
+        };
+        let data: Arc<[u8]> = data.into();
+        let layout = prepare_ktx2(
+            &mut transcoder,
+            data.clone(),
+            &self.transcode_params(settings)?,
+            false,
+        )?;
+        let info = TranscodedInfo::new(&layout, settings);
+        let fallback = unaligned_size_fallback(&layout, &info, settings)?;
+        let metadata = Ktx2Metadata::from_raw(transcoder.key_values());
+        let settings = fallback.as_ref().unwrap_or(settings);
+        transcode_ktx2_image(
+            data,
+            &self.transcode_params(settings)?,
+            settings,
+            &metadata,
+            false,
+            max_transcode_tasks,
+            path,
+        )
+        .await
+    }
+
+    /// Returns the original texture embedded by [`BasisuProcessor`](crate::BasisuProcessor) if the device doesn't support the format it was pre-transcoded to.
+    #[cfg(feature = "processor")]
+    fn processed_fallback(&self, data: &[u8]) -> Option<Vec<u8>> {
+        if !data.starts_with(&KTX2_IDENTIFIER) || is_basisu_ktx2(data) {
+            return None;
+        }
+        let vk_format = u32::from_le_bytes(data.get(12..16)?.try_into().unwrap());
+        let method = crate::processor::ktx2_compression_method(vk_format);
+        if self.supported_compressed_formats.contains(method) {
+            return None;
+        }
+        ktx2_key_value(data, BASISU_ORIGINAL_KEY).map(<[u8]>::to_vec)
+    }
+
+    /// Loads a KTX2 file that isn't Basis Universal encoded with Bevy's [`ImageLoader`], e.g. one pre-transcoded by [`BasisuProcessor`](crate::BasisuProcessor).
+    ///
+    /// Its key/value data is added as the [`Ktx2Metadata`] sub-asset. The [`BASISU_DECODED_LABEL`] copy is transcoded from the embedded original texture.
+    #[cfg(feature = "ktx2")]
+    async fn load_native_ktx2(
+        &self,
+        data: &[u8],
+        settings: &BasisuLoaderSettings,
+        load_context: &mut bevy::asset::LoadContext<'_>,
+    ) -> Result<Image, BasisuLoaderError> {
+        let image_settings = ImageLoaderSettings {
+            format: ImageFormatSetting::Format(ImageFormat::Ktx2),
+            texture_format: None,
+            is_srgb: settings.is_srgb.unwrap_or(true),
+            sampler: settings.sampler.clone(),
+            asset_usage: settings.asset_usage,
+        };
+        let image = self
+            .image_loader
+            .load(
+                &mut bevy::asset::io::SliceReader::new(data),
+                &image_settings,
+                load_context,
+            )
+            .await?;
+        let key_values = ktx2_key_values(data);
+        #[cfg(feature = "processor")]
+        let key_values = key_values
+            .into_iter()
+            .filter(|(key, _)| *key != BASISU_ORIGINAL_KEY)
+            .collect::<Vec<_>>();
+        load_context.add_labeled_asset(
+            KTX2_METADATA_LABEL.to_string(),
+            Ktx2Metadata::from_raw(
+                key_values
+                    .into_iter()
+                    .map(|(key, value)| (key.to_vec(), value.to_vec()))
+                    .collect(),
+            ),
+        );
+        if settings.decoded_copy {
+            #[cfg(feature = "processor")]
+            if let Some(original) = ktx2_key_value(data, BASISU_ORIGINAL_KEY) {
+                let decoded_settings = BasisuLoaderSettings {
+                    asset_usage: RenderAssetUsages::MAIN_WORLD,
+                    force_transcode_target: Some(
+                        if matches!(
+                            image.texture_descriptor.format,
+                            TextureFormat::Bc6hRgbUfloat | TextureFormat::Rgba16Float
+                        ) {
+                            TextureFormat::Rgba16Float
+                        } else {
+                            TextureFormat::Rgba8Unorm
+                        },
+                    ),
+                    // Animated textures are only pre-transcoded as a `D2Array` of all frames.
+                    load_animation_as_array: true,
+                    progressive_preview_size: None,
+                    decoded_copy: false,
+                    ..settings.clone()
+                };
+                let decoded = self
+                    .transcode_image(
+                        original.to_vec(),
+                        &decoded_settings,
+                        load_context.asset_path(),
+                    )
+                    .await?;
+                load_context.add_labeled_asset(BASISU_DECODED_LABEL.to_string(), decoded);
+                return Ok(image);
+            }
+            bevy::log::warn!(
+                "{} isn't Basis Universal encoded and has no original texture to transcode the decoded copy from",
+                load_context.asset_path()
+            );
+        }
+        Ok(image)
+    }
+}
+
+/// Transcodes a KTX2 texture to a single [`Image`] with a fresh transcoder, e.g. the uncompressed copy of [`BasisuLoaderSettings::decoded_copy`].
+///
+/// Animated textures are transcoded as their first frame if `animation` is true, otherwise as a `D2Array` of all frames.
+async fn transcode_ktx2_image(
+    data: Arc<[u8]>,
+    params: &TranscodeParams,
+    settings: &BasisuLoaderSettings,
+    metadata: &Ktx2Metadata,
+    animation: bool,
+    max_transcode_tasks: Option<NonZeroUsize>,
+    path: &AssetPath<'_>,
+) -> Result<Image, BasisuLoaderError> {
+    let mut transcoder = Ktx2Transcoder::new()?;
+    let layout = prepare_ktx2(&mut transcoder, data, params, animation)?;
+    let mut info = TranscodedInfo::new(&layout, settings);
+    if !settings.ignore_orientation_and_swizzle {
+        info.transform = Ktx2Transform::new(metadata, info.format, info.extent, info.levels, path);
+    }
+    let texture = if animation {
+        transcoder.transcode_frame(0, &mut TranscodeState::new()?)?;
+        transcoder.take_texture().unwrap()
+    } else {
+        let slices = transcoder.slices();
+        let mut transcoder = Arc::new(transcoder);
+        transcode_ktx2_slices(&transcoder, 0..slices, max_transcode_tasks).await?;
+        finished_transcoder(&mut transcoder).take_texture().unwrap()
+    };
+    Ok(info.to_image(texture.data, settings))
+}
+
+/// Transcodes the given slices (a level, layer and face each) of a prepared KTX2 texture.
+///
+/// On native platforms they are transcoded in parallel on the [`AsyncComputeTaskPool`], each task with its own transcoder state.
+/// The transcoder is shared with the tasks to keep it and its data alive even if loading is cancelled.
+///
+/// On web they are transcoded in the Web Workers started by [`BasisuLoaderPlugin::web_workers`](crate::BasisuLoaderPlugin::web_workers), or on the main thread without workers.
+async fn transcode_ktx2_slices(
+    transcoder: &Arc<Ktx2Transcoder>,
+    slices: Range<u32>,
+    max_transcode_tasks: Option<NonZeroUsize>,
+) -> Result<(), BasisuLoaderError> {
+    #[cfg(all(
+        target_arch = "wasm32",
+        target_vendor = "unknown",
+        target_os = "unknown",
+    ))]
+    {
+        let max_jobs = max_transcode_tasks.map_or(usize::MAX, NonZeroUsize::get);
+        if transcoder
+            .transcode_slices_in_workers(slices.clone(), max_jobs)
+            .await?
+        {
+            return Ok(());
         }
+        let mut state = TranscodeState::new()?;
+        for slice_index in slices {
+            transcoder.transcode_slice(slice_index, &mut state)?;
+        }
+        Ok(())
+    }
+    #[cfg(not(all(
+        target_arch = "wasm32",
+        target_vendor = "unknown",
+        target_os = "unknown",
+    )))]
+    {
+        let pool = AsyncComputeTaskPool::get();
+        let task_count = max_transcode_tasks
+            .map_or(pool.thread_num(), |max| max.get().min(pool.thread_num()))
+            .clamp(1, slices.len().max(1));
+        let tasks = (0..task_count)
+            .map(|task_index| {
+                let transcoder = transcoder.clone();
+                let slices = slices.clone();
+                pool.spawn(async move {
+                    let mut state = TranscodeState::new()?;
+                    slices
+                        .skip(task_index)
+                        .step_by(task_count)
+                        .try_for_each(|slice_index| {
+                            transcoder.transcode_slice(slice_index, &mut state)
+                        })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut result = Ok(());
+        for task in tasks {
+            result = result.and(task.await);
+        }
+        Ok(result?)
+    }
+}
+
+/// Returns the transcoder once the transcoding tasks are finished.
+fn finished_transcoder(transcoder: &mut Arc<Ktx2Transcoder>) -> &mut Ktx2Transcoder {
+    Arc::get_mut(transcoder).expect("the transcoding tasks are finished")
+}
+
+/// Prepares a KTX2 transcoder, for transcoding single animation frames if `animation` is true.
+pub(crate) fn prepare_ktx2(
+    transcoder: &mut Ktx2Transcoder,
+    data: Arc<[u8]>,
+    params: &TranscodeParams,
+    animation: bool,
+) -> Result<TextureLayout, BasisuLoaderError> {
+    let layout = if animation {
+        transcoder.prepare_animation(data, params)?
+    } else {
+        transcoder.prepare(data, params)?
+    };
+    Ok(layout)
+}
+
+/// Checks that the forced transcode target is a format Basis Universal can transcode to, was compiled with and the device supports.
+fn check_force_transcode_target(
+    settings: &BasisuLoaderSettings,
+    supported_compressed_formats: TextureCompressionMethod,
+) -> Result<(), BasisuLoaderError> {
+    let Some(target) = settings.force_transcode_target else {
+        return Ok(());
+    };
+    let required = match target.remove_srgb_suffix() {
+        TextureFormat::Etc2Rgb8Unorm
+        | TextureFormat::Etc2Rgba8Unorm
+        | TextureFormat::EacR11Unorm
+        | TextureFormat::EacRg11Unorm => Some(TextureCompressionMethod::ETC2),
+        TextureFormat::Bc1RgbaUnorm
+        | TextureFormat::Bc3RgbaUnorm
+        | TextureFormat::Bc4RUnorm
+        | TextureFormat::Bc5RgUnorm
+        | TextureFormat::Bc6hRgbUfloat
+        | TextureFormat::Bc7RgbaUnorm => Some(TextureCompressionMethod::BC),
+        TextureFormat::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::Unorm,
+        } => Some(TextureCompressionMethod::ASTC_LDR),
+        TextureFormat::Astc {
+            block: AstcBlock::B4x4 | AstcBlock::B6x6,
+            channel: AstcChannel::Hdr,
+        } => Some(TextureCompressionMethod::ASTC_HDR),
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba16Float | TextureFormat::Rgb9e5Ufloat => {
+            Some(TextureCompressionMethod::NONE)
+        }
+        _ => None,
+    };
+    match required {
+        Some(required) if supported_compressed_formats & required == required => Ok(()),
+        _ => Err(BasisuLoaderError::UnsupportedTarget {
+            basis_format: None,
+            target: Some(target),
+        }),
+    }
+}
+
+impl From<TranscodeError> for BasisuLoaderError {
+    fn from(error: TranscodeError) -> Self {
+        let basis_format = BasisFormat::from_sys(error.basis_format);
+        // The target format is only known once the header was read.
+        let target = (error.code != TranscodeErrorCode::TRANSCODE_ERROR_INVALID_HEADER)
+            .then(|| try_texture_transcode_format_to_bevy_format(error.target_format))
+            .flatten();
+        match error.code {
+            TranscodeErrorCode::TRANSCODE_ERROR_UNSUPPORTED_BASIS_FORMAT => {
+                Self::UnsupportedBasisFormat(basis_format)
+            }
+            TranscodeErrorCode::TRANSCODE_ERROR_UNSUPPORTED_TARGET => Self::UnsupportedTarget {
+                basis_format,
+                target,
+            },
+            TranscodeErrorCode::TRANSCODE_ERROR_INCOMPATIBLE_IMAGES => Self::IncompatibleImages {
+                layer: error.layer,
+                width: error.width,
+                height: error.height,
+            },
+            TranscodeErrorCode::TRANSCODE_ERROR_TRANSCODE_LEVEL => Self::TranscodeLevel {
+                basis_format,
+                target,
+                level: error.level,
+                layer: error.layer,
+                face: error.face,
+                width: error.width,
+                height: error.height,
+            },
+            TranscodeErrorCode::TRANSCODE_ERROR_ZSTD => Self::Zstd {
+                level: error.level,
+                layer: error.layer,
+                face: error.face,
+            },
+            TranscodeErrorCode::TRANSCODE_ERROR_OUT_OF_MEMORY => Self::OutOfMemory {
+                width: error.width,
+                height: error.height,
+            },
+            _ => Self::InvalidHeader,
+        }
+    }
+}
+
+/// Applies the [`UnalignedSizePolicy`] of a texture whose size isn't a multiple of the block size of the transcode target.
+///
+/// Returns the settings to transcode to an uncompressed format with, if the policy falls back to one.
+fn unaligned_size_fallback(
+    layout: &TextureLayout,
+    info: &TranscodedInfo,
+    settings: &BasisuLoaderSettings,
+) -> Result<Option<BasisuLoaderSettings>, BasisuLoaderError> {
+    if info.is_block_aligned() {
+        return Ok(None);
+    }
+    match settings.unaligned_size {
+        UnalignedSizePolicy::Uncompressed => Ok(Some(BasisuLoaderSettings {
+            force_transcode_target: Some(uncompressed_target(layout)),
+            ..settings.clone()
+        })),
+        UnalignedSizePolicy::Pad => Ok(None),
+        UnalignedSizePolicy::Error => Err(BasisuLoaderError::UnalignedSize {
+            width: info.extent.width,
+            height: info.extent.height,
+            target: info.format,
+        }),
+    }
+}
+
+/// The uncompressed format a texture can always be transcoded to, `Rgba16Float` for HDR and `Rgba8Unorm` for LDR textures.
+fn uncompressed_target(layout: &TextureLayout) -> TextureFormat {
+    if BasisFormat::from_sys(layout.basis_format).is_some_and(BasisFormat::is_hdr) {
+        TextureFormat::Rgba16Float
+    } else {
+        TextureFormat::Rgba8Unorm
+    }
+}
+
+/// Returns the settings to transcode the copy of [`BasisuLoaderSettings::decoded_copy`] with.
+fn decoded_copy_settings(
+    layout: &TextureLayout,
+    settings: &BasisuLoaderSettings,
+) -> BasisuLoaderSettings {
+    BasisuLoaderSettings {
+        asset_usage: RenderAssetUsages::MAIN_WORLD,
+        force_transcode_target: Some(uncompressed_target(layout)),
+        ..settings.clone()
+    }
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const KTX2_SUPERCOMPRESSION_BASISLZ: u32 = 1;
+const KTX2_DF_MODEL_ETC1S: u8 = 163;
+const KTX2_DF_MODEL_UASTC_LDR_4X4: u8 = 166;
+const KTX2_DF_MODEL_UASTC_HDR_4X4: u8 = 167;
+const KTX2_DF_MODEL_UASTC_HDR_6X6_INTERMEDIATE: u8 = 168;
+
+/// Returns whether `data` is a KTX2 file containing a Basis Universal payload.
+///
+/// Basis Universal textures have an undefined `vkFormat` and are either BasisLZ supercompressed (ETC1S) or use a UASTC color model in the data format descriptor.
+/// Other KTX2 files (e.g. BC7 or ASTC) contain GPU-native data and don't need transcoding.
+pub fn is_basisu_ktx2(data: &[u8]) -> bool {
+    let read_u32 = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    };
+    if data.get(..12) != Some(&KTX2_IDENTIFIER[..]) || read_u32(12) != Some(0) {
+        return false;
+    }
+    if read_u32(44) == Some(KTX2_SUPERCOMPRESSION_BASISLZ) {
+        return true;
+    }
+    // The color model is the first byte after the DFD total size and the descriptor block header.
+    let Some(dfd_offset) = read_u32(48) else {
+        return false;
+    };
+    let color_model = (dfd_offset as usize)
+        .checked_add(12)
+        .and_then(|offset| data.get(offset))
+        .copied();
+    matches!(
+        color_model,
+        Some(
+            KTX2_DF_MODEL_ETC1S
+                | KTX2_DF_MODEL_UASTC_LDR_4X4
+                | KTX2_DF_MODEL_UASTC_HDR_4X4
+                | KTX2_DF_MODEL_UASTC_HDR_6X6_INTERMEDIATE
+        )
+    )
+}
+
+fn ktx2_layer_count(data: &[u8]) -> u32 {
+    data.get(32..36)
+        .map_or(0, |b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// Returns the key/value pairs of a KTX2 file in file order, without parsing the rest of the file.
+///
+/// Keys are returned without their NUL terminator. Reading stops at the first malformed pair.
+pub(crate) fn ktx2_key_values(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let read_u32 = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+    };
+    let mut key_values = Vec::new();
+    let Some(kvd) = read_u32(56)
+        .zip(read_u32(60))
+        .and_then(|(offset, len)| data.get(offset..offset.checked_add(len)?))
+    else {
+        return key_values;
+    };
+    let mut offset = 0;
+    while offset + 4 <= kvd.len() {
+        let len = u32::from_le_bytes(kvd[offset..offset + 4].try_into().unwrap()) as usize;
+        let Some(key_value) = (offset + 4)
+            .checked_add(len)
+            .and_then(|end| kvd.get(offset + 4..end))
+        else {
+            break;
+        };
+        // The key is NUL terminated and each key/value pair is padded to 4 bytes.
+        let Some(nul) = key_value.iter().position(|&b| b == 0) else {
+            break;
+        };
+        key_values.push((&key_value[..nul], &key_value[nul + 1..]));
+        let Some(next) = len
+            .checked_next_multiple_of(4)
+            .and_then(|len| (offset + 4).checked_add(len))
+        else {
+            break;
+        };
+        offset = next;
+    }
+    key_values
+}
+
+/// Returns the value of a key in the key/value data of a KTX2 file, without parsing the rest of the file.
+pub(crate) fn ktx2_key_value<'a>(data: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+    ktx2_key_values(data)
+        .into_iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| value)
+}
+
+/// The Basis Universal format a texture is encoded in, i.e. the format it's transcoded from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BasisFormat {
+    /// ETC1S, LDR. Usually BasisLZ supercompressed.
+    Etc1s,
+    /// UASTC LDR 4x4.
+    UastcLdr4x4,
+    /// UASTC HDR 4x4.
+    UastcHdr4x4,
+    /// ASTC HDR 6x6.
+    AstcHdr6x6,
+    /// UASTC HDR 6x6 intermediate.
+    UastcHdr6x6Intermediate,
+}
+
+impl BasisFormat {
+    fn from_sys(format: BasisTexFormat) -> Option<Self> {
+        match format {
+            BasisTexFormat::cETC1S => Some(Self::Etc1s),
+            BasisTexFormat::cUASTC4x4 => Some(Self::UastcLdr4x4),
+            BasisTexFormat::cUASTC_HDR_4x4 => Some(Self::UastcHdr4x4),
+            BasisTexFormat::cASTC_HDR_6x6 => Some(Self::AstcHdr6x6),
+            BasisTexFormat::cASTC_HDR_6x6_INTERMEDIATE => Some(Self::UastcHdr6x6Intermediate),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_sys(self) -> BasisTexFormat {
+        match self {
+            Self::Etc1s => BasisTexFormat::cETC1S,
+            Self::UastcLdr4x4 => BasisTexFormat::cUASTC4x4,
+            Self::UastcHdr4x4 => BasisTexFormat::cUASTC_HDR_4x4,
+            Self::AstcHdr6x6 => BasisTexFormat::cASTC_HDR_6x6,
+            Self::UastcHdr6x6Intermediate => BasisTexFormat::cASTC_HDR_6x6_INTERMEDIATE,
+        }
+    }
+
+    fn is_hdr(self) -> bool {
+        !matches!(self, Self::Etc1s | Self::UastcLdr4x4)
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum ChannelType {
     #[default]
@@ -46,6 +679,35 @@ pub enum ChannelType {
     R,
 }
 
+/// Basis Universal decode options, trading transcode speed for quality.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Use slower but higher quality transcoding where Basis Universal has it, e.g. from UASTC LDR to BC1, BC3 or ETC.
+    pub high_quality: bool,
+    /// Disable the filter that reduces chroma blocking artifacts when transcoding ETC1S to uncompressed formats.
+    ///
+    /// This is the only deblocking filter of the vendored Basis Universal version, disabling it makes transcoding faster.
+    pub disable_etc1s_chroma_filtering: bool,
+    /// Transcode the alpha data instead of the color data when the target is an opaque format, e.g. BC4 or ETC2 EAC R11.
+    pub transcode_alpha_to_opaque: bool,
+}
+
+impl DecodeOptions {
+    fn decode_flags(self) -> DecodeFlags {
+        let mut flags = DecodeFlags::DECODE_FLAGS_NONE;
+        if self.high_quality {
+            flags |= DecodeFlags::DECODE_FLAGS_HIGH_QUALITY;
+        }
+        if self.disable_etc1s_chroma_filtering {
+            flags |= DecodeFlags::DECODE_FLAGS_NO_ETC1S_CHROMA_FILTERING;
+        }
+        if self.transcode_alpha_to_opaque {
+            flags |= DecodeFlags::DECODE_FLAGS_TRANSCODE_ALPHA_DATA_TO_OPAQUE_FORMATS;
+        }
+        flags
+    }
+}
+
 /// Settings for loading an [`Image`] using an [`BasisuLoader`].
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct BasisuLoaderSettings {
@@ -57,11 +719,11 @@ pub struct BasisuLoaderSettings {
     pub asset_usage: RenderAssetUsages,
     /// Whether the texture should be created as sRGB format.
     ///
-    /// If `None`, it will be determined by the KTX2 data format descriptor transfer function.
+    /// If `None`, it will be determined by the KTX2 data format descriptor transfer function, or the sRGB flag of the `.basis` header.
     pub is_srgb: Option<bool>,
     /// The channel type hint for transcode target selection.
     ///
-    /// If [`ChannelType::Auto`], it will be determined by the KTX2 data format descriptor channel type.
+    /// If [`ChannelType::Auto`], it will be determined by the KTX2 data format descriptor channel type, or whether a `.basis` file has alpha slices.
     /// Note: This will be ignored when the transcode target format is not ETC2 or BC4/BC5 and usually has no effect for UASTC textures. See [`BasisuLoaderPlugin`](crate::BasisuLoaderPlugin) for more information about the transcode targets.
     pub channel_type_hint: ChannelType,
     /// Forcibly transcode to a specific [`TextureFormat`] if it's not `None`. Otherwise the format will be selected automatically.
@@ -70,6 +732,43 @@ pub struct BasisuLoaderSettings {
     /// Srgb-ness is ignored and will be determined by `is_srgb`.
     #[serde(skip)]
     pub force_transcode_target: Option<TextureFormat>,
+    /// Skip this many of the largest mip levels, the remaining levels are neither transcoded nor uploaded.
+    ///
+    /// The smallest mip level is always kept. Useful as a texture quality setting for low-end devices.
+    pub skip_mip_levels: u32,
+    /// Skip the mip levels whose width or height is larger than this, in addition to `skip_mip_levels`.
+    ///
+    /// The smallest mip level is always kept, even if it's larger. If `None`, the size is not limited.
+    pub max_resolution: Option<u32>,
+    /// Load the smallest mip levels first.
+    ///
+    /// If `Some(size)`, the loader first returns an [`Image`] that only contains the mip levels whose width and height are at most `size`,
+    /// then replaces it with the full mip chain once the remaining levels are transcoded. Only applies to KTX2 textures with multiple mip levels.
+    pub progressive_preview_size: Option<u32>,
+    /// Don't apply the `KTXorientation` and `KTXswizzle` key/values of KTX2 textures.
+    ///
+    /// By default, textures with an up orientation (e.g. authored in GL conventions) are flipped vertically and swizzled channels are reordered.
+    /// Flipping is supported for uncompressed and BC1/BC3/BC4/BC5 targets and swizzling for `Rgba8Unorm` and `Rgba16Float` targets, a warning is logged otherwise.
+    pub ignore_orientation_and_swizzle: bool,
+    /// Load animated KTX2 textures (with `KTXanimData`) as a `D2Array` of all frames.
+    ///
+    /// By default, only the first frame is loaded as the [`Image`] and the frames are played by a [`BasisuAnimationPlayer`](crate::BasisuAnimationPlayer)
+    /// using the [`BasisuAnimation`] sub-asset labeled [`BASISU_ANIMATION_LABEL`].
+    pub load_animation_as_array: bool,
+    /// What to do when the texture size isn't a multiple of the block size of the transcode target.
+    pub unaligned_size: UnalignedSizePolicy,
+    /// Basis Universal decode options, e.g. to trade transcode speed for quality per asset.
+    pub decode_options: DecodeOptions,
+    /// The transcode targets to try first, checked before [`BasisuLoaderPlugin::target_priority`](crate::BasisuLoaderPlugin::target_priority).
+    ///
+    /// E.g. prefer [`TranscodeTarget::Uncompressed`] for UI atlases.
+    pub target_priority: Vec<TargetPriorityRule>,
+    /// Also transcode the texture to `Rgba8Unorm`, or `Rgba16Float` for HDR textures, and add it as an [`Image`] sub-asset labeled [`BASISU_DECODED_LABEL`].
+    ///
+    /// The copy uses [`RenderAssetUsages::MAIN_WORLD`] and isn't uploaded, so gameplay code can read its pixels with [`Image::get_color_at`] while the main [`Image`] stays GPU compressed.
+    /// It has the same mip levels, layers and sRGB-ness as the main [`Image`], so with [`Self::progressive_preview_size`] it's replaced along with it.
+    /// Only applies to Basis Universal textures.
+    pub decoded_copy: bool,
 }
 
 /// An error when loading an image using [`BasisuLoader`].
@@ -79,9 +778,59 @@ pub enum BasisuLoaderError {
     /// An error occurred while trying to load the image bytes.
     #[error("Failed to load image bytes: {0}")]
     Io(#[from] std::io::Error),
-    /// An error occurred while trying to decode the image bytes.
-    #[error("BasisU failed to transcode texture: {0}")]
-    TranscodingError(&'static str),
+    /// The KTX2 or `.basis` header, level index or ETC1S global data is invalid.
+    #[error("Invalid BasisU texture header")]
+    InvalidHeader,
+    /// Basis Universal has no transcode target for the format, e.g. it's disabled at compile time.
+    #[error("BasisU can't transcode {0:?} textures")]
+    UnsupportedBasisFormat(Option<BasisFormat>),
+    /// The transcode target isn't supported by Basis Universal for the format or isn't supported by the device.
+    ///
+    /// `basis_format` is `None` if the texture wasn't read yet, e.g. when the forced target isn't supported by the device.
+    #[error("BasisU can't transcode {basis_format:?} textures to {target:?} on this device")]
+    UnsupportedTarget {
+        basis_format: Option<BasisFormat>,
+        target: Option<TextureFormat>,
+    },
+    /// The images of a `.basis` file have different sizes or mip level counts, so they can't be loaded as a single texture.
+    #[error(
+        "The images of the .basis file don't have the same size and mip levels, image {layer} is {width}x{height}"
+    )]
+    IncompatibleImages { layer: u32, width: u32, height: u32 },
+    /// Transcoding a mip level of a layer and face failed, usually because the data is corrupt.
+    #[error(
+        "BasisU failed to transcode level {level} ({width}x{height}), layer {layer}, face {face} from {basis_format:?} to {target:?}"
+    )]
+    TranscodeLevel {
+        basis_format: Option<BasisFormat>,
+        target: Option<TextureFormat>,
+        level: u32,
+        layer: u32,
+        face: u32,
+        width: u32,
+        height: u32,
+    },
+    /// The zstd supercompressed data of a mip level couldn't be decompressed.
+    #[error(
+        "Failed to decompress the zstd supercompressed level {level} (layer {layer}, face {face})"
+    )]
+    Zstd { level: u32, layer: u32, face: u32 },
+    /// The transcoded texture couldn't be allocated.
+    #[error("Out of memory while transcoding a {width}x{height} BasisU texture")]
+    OutOfMemory { width: u32, height: u32 },
+    /// The texture size isn't a multiple of the block size of the transcode target and [`UnalignedSizePolicy::Error`] is used.
+    #[error(
+        "The BasisU texture size {width}x{height} isn't a multiple of the block size of {target:?}"
+    )]
+    UnalignedSize {
+        width: u32,
+        height: u32,
+        target: TextureFormat,
+    },
+    /// An error occurred while loading a KTX2 texture that isn't Basis Universal encoded.
+    #[cfg(feature = "ktx2")]
+    #[error("Failed to load KTX2 texture: {0}")]
+    ImageLoader(#[from] ImageLoaderError),
 }
 
 impl AssetLoader for BasisuLoader {
@@ -95,109 +844,489 @@ impl AssetLoader for BasisuLoader {
         &self,
         reader: &mut dyn bevy::asset::io::Reader,
         settings: &Self::Settings,
-        _load_context: &mut bevy::asset::LoadContext<'_>,
+        load_context: &mut bevy::asset::LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
+        // A reload drops the full mip chain of earlier progressive loads, even if it fails.
+        if let Some(sender) = &self.progressive_sender {
+            sender.cancel(load_context.asset_path());
+        }
         let mut data = Vec::new();
         reader.read_to_end(&mut data).await?;
 
-        // SAFETY: Ensure the transcoding code is correct.
-        let (out_data, out_format, extent, levels, view_dimension) = unsafe {
-            let _span = bevy::log::info_span!("Transcoding BasisU Texture").entered();
-            let time = if log::STATIC_MAX_LEVEL >= log::LevelFilter::Debug {
-                Some(bevy::platform::time::Instant::now())
-            } else {
-                None
-            };
+        #[cfg(feature = "processor")]
+        if let Some(original) = self.processed_fallback(&data) {
+            bevy::log::debug!(
+                "{} was pre-transcoded to a format this device doesn't support, transcoding the original texture",
+                load_context.asset_path()
+            );
+            data = original;
+        }
+        #[cfg(feature = "ktx2")]
+        if data.starts_with(&KTX2_IDENTIFIER) && !is_basisu_ktx2(&data) {
+            return self.load_native_ktx2(&data, settings, load_context).await;
+        }
 
-            let transcoder = bevy_basisu_loader_sys::ktx2_transcoder_new();
-            if transcoder.is_null() {
-                return Err(BasisuLoaderError::TranscodingError("ktx2_transcoder_new"));
+        let span = bevy::log::info_span!("Transcoding BasisU Texture");
+        let time = (log::STATIC_MAX_LEVEL >= log::LevelFilter::Debug
+            || self.stats_sender.is_some())
+        .then(Instant::now);
+        let input_bytes = data.len();
+        let stats_sender = self.stats_sender.as_ref();
+
+        let mut transcoder = Ktx2Transcoder::new()?;
+
+        // Files without the KTX2 identifier are treated as legacy `.basis` files.
+        if !data.starts_with(&KTX2_IDENTIFIER) {
+            let _span = span.enter();
+            let mut texture =
+                transcoder.transcode_basis(&data, &self.transcode_params(settings)?)?;
+            let mut info = TranscodedInfo::new(&texture.layout, settings);
+            let fallback_settings;
+            let settings = match unaligned_size_fallback(&texture.layout, &info, settings)? {
+                None => settings,
+                Some(fallback) => {
+                    fallback_settings = fallback;
+                    texture = transcoder
+                        .transcode_basis(&data, &self.transcode_params(&fallback_settings)?)?;
+                    info = TranscodedInfo::new(&texture.layout, &fallback_settings);
+                    &fallback_settings
+                }
+            };
+            if let Some(padding) = info.pad(load_context.asset_path()) {
+                load_context.add_labeled_asset(BASISU_PADDING_LABEL.to_string(), padding);
             }
-            if !bevy_basisu_loader_sys::ktx2_transcoder_transcode_image(
-                transcoder,
-                data,
-                self.supported_compressed_formats,
-                bevy_basisu_loader_sys::ChannelType(settings.channel_type_hint as u8),
-                texture_bevy_format_to_transcode_format(settings.force_transcode_target),
-            ) {
-                return Err(BasisuLoaderError::TranscodingError(
-                    "ktx2_transcoder_transcode_image",
-                ));
+            if settings.decoded_copy {
+                let decoded_settings = decoded_copy_settings(&texture.layout, settings);
+                let decoded = transcoder
+                    .transcode_basis(&data, &self.transcode_params(&decoded_settings)?)?;
+                let decoded = TranscodedInfo::new(&decoded.layout, &decoded_settings)
+                    .to_image(decoded.data, &decoded_settings);
+                load_context.add_labeled_asset(BASISU_DECODED_LABEL.to_string(), decoded);
             }
+            let image = info.to_image(texture.data, settings);
+            info.log_transcoded(image.data.as_ref().unwrap().len(), time);
+            info.record_transcoded(
+                stats_sender,
+                input_bytes,
+                image.data.as_ref().unwrap().len(),
+                time,
+            );
+            return Ok(image);
+        }
 
-            let is_srgb =
-                settings
-                    .is_srgb
-                    .unwrap_or(bevy_basisu_loader_sys::ktx2_transcoder_get_r_is_srgb(
-                        transcoder,
-                    ));
-            let target_format =
-                bevy_basisu_loader_sys::ktx2_transcoder_get_r_target_format(transcoder);
-
-            let width = bevy_basisu_loader_sys::ktx2_transcoder_get_r_width(transcoder);
-            let height = bevy_basisu_loader_sys::ktx2_transcoder_get_r_height(transcoder);
-            let levels = bevy_basisu_loader_sys::ktx2_transcoder_get_r_levels(transcoder);
-            let layers = bevy_basisu_loader_sys::ktx2_transcoder_get_r_layers(transcoder);
-            let faces = bevy_basisu_loader_sys::ktx2_transcoder_get_r_faces(transcoder);
-            let dst_bytes = bevy_basisu_loader_sys::ktx2_transcoder_get_r_dst_buf(transcoder);
-
-            let view_dimension = if layers == 0 {
-                if faces == 1 {
-                    TextureViewDimension::D2
-                } else if faces == 6 {
-                    TextureViewDimension::Cube
-                } else {
-                    unreachable!()
+        let anim_data = ktx2_key_value(&data, b"KTXanimData").map(AnimData::parse);
+        let animation = anim_data.filter(|_| !settings.load_animation_as_array);
+        let data: Arc<[u8]> = data.into();
+        let fallback_settings;
+        // The destination buffer is only allocated once the transcode cache was missed.
+        let prepare = |transcoder: &mut Ktx2Transcoder, params: &TranscodeParams| {
+            if animation.is_some() {
+                transcoder.prepare_animation(data.clone(), params)
+            } else {
+                transcoder.prepare_without_dst_buf(data.clone(), params)
+            }
+        };
+        let (settings, params, mut info) = {
+            let _span = span.enter();
+            let params = self.transcode_params(settings)?;
+            let layout = prepare(&mut transcoder, &params)?;
+            let info = TranscodedInfo::new(&layout, settings);
+            match unaligned_size_fallback(&layout, &info, settings)? {
+                None => (settings, params, info),
+                Some(fallback) => {
+                    fallback_settings = fallback;
+                    let params = self.transcode_params(&fallback_settings)?;
+                    let layout = prepare(&mut transcoder, &params)?;
+                    (
+                        &fallback_settings,
+                        params,
+                        TranscodedInfo::new(&layout, &fallback_settings),
+                    )
                 }
-            } else if faces == 1 {
-                TextureViewDimension::D2Array
-            } else if faces == 6 {
-                TextureViewDimension::CubeArray
+            }
+        };
+        let slices = transcoder.slices();
+        let metadata = Ktx2Metadata::from_raw(transcoder.key_values());
+        if !settings.ignore_orientation_and_swizzle {
+            info.transform = Ktx2Transform::new(
+                &metadata,
+                info.format,
+                info.extent,
+                info.levels,
+                load_context.asset_path(),
+            );
+        }
+        // The frames of ETC1S video depend on the previous frame, so they are transcoded in order.
+        let max_transcode_tasks = if anim_data.is_some() {
+            NonZeroUsize::new(1)
+        } else {
+            self.max_transcode_tasks
+        };
+        load_context.add_labeled_asset(KTX2_METADATA_LABEL.to_string(), metadata.clone());
+        if let Some(padding) = info.pad(load_context.asset_path()) {
+            load_context.add_labeled_asset(BASISU_PADDING_LABEL.to_string(), padding);
+        }
+
+        // Only the full mip chain is cached, textures loaded from the cache aren't loaded progressively.
+        let cache = self
+            .transcode_cache
+            .clone()
+            .filter(|_| animation.is_none())
+            .map(|cache| {
+                let key = TranscodeCache::key(&data, &params, &transcoder.layout().unwrap());
+                (cache, key)
+            });
+        let cached = cache
+            .as_ref()
+            .and_then(|(cache, key)| cache.read(key, transcoder.slice_range(0..slices).end));
+        if cached.is_none() {
+            transcoder.alloc_dst_buf()?;
+        }
+        let preview_level = match (&self.progressive_sender, settings.progressive_preview_size) {
+            _ if animation.is_some() || cached.is_some() => 0,
+            (Some(_), Some(size)) => info.first_level_within(size),
+            (None, Some(_)) => {
+                bevy::log::warn!(
+                    "Progressive loading requires the `BasisuLoader` registered by `BasisuLoaderPlugin`, loading all mip levels at once"
+                );
+                0
+            }
+            _ => 0,
+        };
+
+        // With a preview, the decoded copy only has the preview mip levels until the full mip chain is transcoded.
+        let decoded_copy = settings.decoded_copy.then(|| {
+            let layout = transcoder.layout().unwrap();
+            let settings = decoded_copy_settings(&layout, settings);
+            let preview_settings = BasisuLoaderSettings {
+                max_resolution: Some(
+                    (layout.width >> preview_level)
+                        .max(layout.height >> preview_level)
+                        .max(1),
+                ),
+                ..settings.clone()
+            };
+            (settings, preview_settings)
+        });
+        if let Some((decoded_settings, preview_settings)) = &decoded_copy {
+            let settings = if preview_level == 0 {
+                decoded_settings
             } else {
-                unreachable!()
+                preview_settings
             };
-            let extent = Extent3d {
-                width,
-                height,
-                depth_or_array_layers: layers.max(1) * faces,
+            let decoded = transcode_ktx2_image(
+                data.clone(),
+                &self.transcode_params(settings)?,
+                settings,
+                &metadata,
+                animation.is_some(),
+                max_transcode_tasks,
+                load_context.asset_path(),
+            )
+            .instrument(span.clone())
+            .await?;
+            load_context.add_labeled_asset(BASISU_DECODED_LABEL.to_string(), decoded);
+        }
+
+        if let Some(anim_data) = animation {
+            let image = {
+                let _span = span.enter();
+                transcoder.transcode_frame(0, &mut TranscodeState::new()?)?;
+                info.to_image(transcoder.take_texture().unwrap().data, settings)
             };
+            info.log_transcoded(image.data.as_ref().unwrap().len(), time);
+            info.record_transcoded(
+                stats_sender,
+                input_bytes,
+                image.data.as_ref().unwrap().len(),
+                time,
+            );
+            load_context.add_labeled_asset(
+                BASISU_ANIMATION_LABEL.to_string(),
+                BasisuAnimation {
+                    frames: ktx2_layer_count(&data).max(1),
+                    frame_duration: anim_data.frame_duration,
+                    loop_count: anim_data.loop_count,
+                    data,
+                    params,
+                    settings: settings.clone(),
+                    info,
+                },
+            );
+            return Ok(image);
+        }
 
-            bevy_basisu_loader_sys::ktx2_transcoder_delete(transcoder);
-            let out_format = texture_transcode_format_to_bevy_format(target_format, is_srgb);
-            if log::STATIC_MAX_LEVEL >= log::LevelFilter::Debug {
-                bevy::log::debug!(
-                    "Transcoded a basisu texture with dst_bytes: {:?}, dst_format: {:?}, extent: {:?}, levels: {:?}, view_dimension: {:?}, in {:?}",
-                    dst_bytes.len(),
-                    out_format,
-                    extent,
-                    levels,
-                    view_dimension,
-                    time.unwrap_unchecked().elapsed()
-                );
+        if let Some(cached) = cached {
+            bevy::log::debug!(
+                "Loaded {} from the transcode cache",
+                load_context.asset_path()
+            );
+            info.record_cached(stats_sender, cached.len());
+            return Ok(info.to_image(cached, settings));
+        }
+
+        let mut transcoder = Arc::new(transcoder);
+        if preview_level == 0 {
+            transcode_ktx2_slices(&transcoder, 0..slices, max_transcode_tasks)
+                .instrument(span.clone())
+                .await?;
+            let texture = finished_transcoder(&mut transcoder).take_texture().unwrap();
+            if let Some((cache, key)) = &cache {
+                cache.write(key, &texture.data);
             }
+            let image = info.to_image(texture.data, settings);
+            info.log_transcoded(image.data.as_ref().unwrap().len(), time);
+            info.record_transcoded(
+                stats_sender,
+                input_bytes,
+                image.data.as_ref().unwrap().len(),
+                time,
+            );
+            return Ok(image);
+        }
+
+        // Slices are ordered by level, so the mip tail is at the end of the destination buffer. Each level has a slice per layer and face.
+        let first_preview_slice = info.extent.depth_or_array_layers * preview_level;
+        transcode_ktx2_slices(
+            &transcoder,
+            first_preview_slice..slices,
+            max_transcode_tasks,
+        )
+        .instrument(span.clone())
+        .await?;
+        let preview_info = info.mip_tail(preview_level);
+        let range = transcoder.slice_range(first_preview_slice..slices);
+        let preview = preview_info.to_image(
+            finished_transcoder(&mut transcoder)
+                .texture_range(range)
+                .unwrap(),
+            settings,
+        );
+        preview_info.log_transcoded(preview.data.as_ref().unwrap().len(), time);
 
-            (dst_bytes, out_format, extent, levels, view_dimension)
+        let sender = self.progressive_sender.clone().unwrap();
+        let decoded_params = decoded_copy
+            .map(|(decoded_settings, _)| {
+                self.transcode_params(&decoded_settings)
+                    .map(|params| (decoded_settings, params))
+            })
+            .transpose()?;
+        let stats_sender = self.stats_sender.clone();
+        let path = load_context.asset_path().clone();
+        let generation = sender.start(&path);
+        let settings = settings.clone();
+        let task = async move {
+            let result =
+                transcode_ktx2_slices(&transcoder, 0..first_preview_slice, max_transcode_tasks)
+                    .await;
+            match result {
+                Ok(()) => {
+                    let decoded = match decoded_params {
+                        Some((decoded_settings, params)) => transcode_ktx2_image(
+                            data,
+                            &params,
+                            &decoded_settings,
+                            &metadata,
+                            false,
+                            max_transcode_tasks,
+                            &path,
+                        )
+                        .await
+                        .inspect_err(|err| {
+                            bevy::log::error!(
+                                "Failed to transcode the decoded copy of {path}: {err}"
+                            );
+                        })
+                        .ok(),
+                        None => None,
+                    };
+                    let texture = finished_transcoder(&mut transcoder).take_texture().unwrap();
+                    if let Some((cache, key)) = &cache {
+                        cache.write(key, &texture.data);
+                    }
+                    let image = info.to_image(texture.data, &settings);
+                    info.log_transcoded(image.data.as_ref().unwrap().len(), time);
+                    info.record_transcoded(
+                        stats_sender.as_ref(),
+                        input_bytes,
+                        image.data.as_ref().unwrap().len(),
+                        time,
+                    );
+                    sender.send(ProgressiveImage {
+                        path,
+                        generation,
+                        image,
+                        decoded,
+                    });
+                }
+                Err(err) => {
+                    bevy::log::error!("Failed to transcode the full mip chain of {path}: {err}");
+                    sender.finish(&path, generation);
+                }
+            }
+        }
+        .instrument(span);
+        AsyncComputeTaskPool::get().spawn(task).detach();
+
+        Ok(preview)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        EXTENSIONS
+    }
+}
+
+/// The layout of a transcoded texture.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TranscodedInfo {
+    format: TextureFormat,
+    extent: Extent3d,
+    levels: u32,
+    view_dimension: TextureViewDimension,
+    transform: Ktx2Transform,
+}
+
+impl TranscodedInfo {
+    /// Converts the layout of a prepared or transcoded texture.
+    pub(crate) fn new(layout: &TextureLayout, settings: &BasisuLoaderSettings) -> Self {
+        let is_srgb = settings.is_srgb.unwrap_or(layout.is_srgb);
+        let TextureLayout {
+            width,
+            height,
+            levels,
+            layers,
+            faces,
+            target_format,
+            ..
+        } = *layout;
+
+        let view_dimension = if layers == 0 {
+            if faces == 1 {
+                TextureViewDimension::D2
+            } else if faces == 6 {
+                TextureViewDimension::Cube
+            } else {
+                unreachable!()
+            }
+        } else if faces == 1 {
+            TextureViewDimension::D2Array
+        } else if faces == 6 {
+            TextureViewDimension::CubeArray
+        } else {
+            unreachable!()
+        };
+        let extent = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: layers.max(1) * faces,
+        };
+
+        Self {
+            format: texture_transcode_format_to_bevy_format(target_format, is_srgb),
+            extent,
+            levels,
+            view_dimension,
+            transform: Ktx2Transform::default(),
+        }
+    }
+
+    /// Returns the first mip level whose width and height are at most `size`, or the last level if there is none.
+    ///
+    /// Only levels whose size is a multiple of the block size can be the first level of a texture.
+    fn first_level_within(&self, size: u32) -> u32 {
+        let mut levels = (0..self.levels).filter(|&level| self.mip_tail(level).is_block_aligned());
+        levels
+            .clone()
+            .find(|&level| {
+                (self.extent.width >> level).max(1) <= size
+                    && (self.extent.height >> level).max(1) <= size
+            })
+            .or_else(|| levels.next_back())
+            .unwrap_or(0)
+    }
+
+    fn is_block_aligned(&self) -> bool {
+        self.extent == self.extent.physical_size(self.format)
+    }
+
+    /// Pads the size up to the block size if it isn't a multiple of it, see [`UnalignedSizePolicy::Pad`].
+    ///
+    /// The mip levels whose block count would change are dropped.
+    fn pad(&mut self, path: &AssetPath) -> Option<BasisuPadding> {
+        if self.is_block_aligned() {
+            return None;
+        }
+        let padded = self.extent.physical_size(self.format);
+        let (block_width, block_height) = self.format.block_dimensions();
+        let blocks =
+            |size: u32, level: u32, block_size: u32| (size >> level).max(1).div_ceil(block_size);
+        let levels = (0..self.levels)
+            .take_while(|&level| {
+                blocks(self.extent.width, level, block_width)
+                    == blocks(padded.width, level, block_width)
+                    && blocks(self.extent.height, level, block_height)
+                        == blocks(padded.height, level, block_height)
+            })
+            .count() as u32;
+        if levels < self.levels {
+            bevy::log::warn!(
+                "Dropping {} mip levels of {path} because its size isn't a multiple of the block size of {:?}",
+                self.levels - levels,
+                self.format
+            );
+        }
+        let padding = BasisuPadding {
+            size: UVec2::new(self.extent.width, self.extent.height),
+            padded_size: UVec2::new(padded.width, padded.height),
         };
+        self.extent = padded;
+        self.levels = levels;
+        Some(padding)
+    }
+
+    /// The size of the transcoded data of all levels, layers and faces.
+    fn data_len(&self) -> usize {
+        (0..self.levels)
+            .map(|level| {
+                let size = self.extent.mip_level_size(level, TextureDimension::D2);
+                let (block_width, block_height) = self.format.block_dimensions();
+                let blocks = size.width.div_ceil(block_width) * size.height.div_ceil(block_height);
+                blocks as usize
+                    * self.format.block_copy_size(None).unwrap() as usize
+                    * size.depth_or_array_layers as usize
+            })
+            .sum()
+    }
+
+    /// Returns the layout of the mip levels starting at `first_level`.
+    fn mip_tail(&self, first_level: u32) -> Self {
+        Self {
+            extent: Extent3d {
+                width: (self.extent.width >> first_level).max(1),
+                height: (self.extent.height >> first_level).max(1),
+                depth_or_array_layers: self.extent.depth_or_array_layers,
+            },
+            levels: self.levels - first_level,
+            ..*self
+        }
+    }
+
+    pub(crate) fn to_image(self, mut data: Vec<u8>, settings: &BasisuLoaderSettings) -> Image {
+        // Padding may have dropped the smallest mip levels, which come last.
+        data.truncate(self.data_len());
+        if !self.transform.is_identity() {
+            self.transform
+                .apply(&mut data, self.format, self.extent, self.levels);
+        }
         let mut image = Image {
             data: None,
             data_order: TextureDataOrder::MipMajor,
             texture_descriptor: TextureDescriptor {
-                // Note: we must give wgpu the logical texture dimensions, so it can correctly compute mip sizes.
-                // However this currently causes wgpu to panic if the dimensions aren't a multiple of blocksize.
-                // See https://github.com/gfx-rs/wgpu/issues/7677 for more context.
-                size: {
-                    #[cfg(debug_assertions)]
-                    if extent != extent.physical_size(out_format) {
-                        bevy::log::error!(
-                            "BasisU texture size has to be a multiple of block size to ensure correct mip levels transcoding, otherwise it will panic for now. This is due to a wgpu limitation, see https://github.com/gfx-rs/wgpu/issues/7677"
-                        );
-                    }
-                    extent
-                },
-                format: out_format,
+                size: self.extent,
+                format: self.format,
                 dimension: TextureDimension::D2,
                 label: None,
-                mip_level_count: levels,
+                mip_level_count: self.levels,
                 sample_count: 1,
                 usage: TextureUsages::TEXTURE_BINDING
                     | TextureUsages::COPY_DST
@@ -206,18 +1335,63 @@ impl AssetLoader for BasisuLoader {
             },
             sampler: settings.sampler.clone(),
             texture_view_descriptor: Some(TextureViewDescriptor {
-                dimension: Some(view_dimension),
+                dimension: Some(self.view_dimension),
                 ..Default::default()
             }),
             asset_usage: settings.asset_usage,
             copy_on_resize: false,
         };
-        image.data = Some(out_data);
-        Ok(image)
+        image.data = Some(data);
+        image
     }
 
-    fn extensions(&self) -> &[&str] {
-        &["basisu_ktx2"]
+    fn log_transcoded(&self, dst_bytes: usize, time: Option<Instant>) {
+        if log::STATIC_MAX_LEVEL >= log::LevelFilter::Debug
+            && let Some(time) = time
+        {
+            bevy::log::debug!(
+                "Transcoded a basisu texture with dst_bytes: {:?}, dst_format: {:?}, extent: {:?}, levels: {:?}, view_dimension: {:?}, in {:?}",
+                dst_bytes,
+                self.format,
+                self.extent,
+                self.levels,
+                self.view_dimension,
+                time.elapsed()
+            );
+        }
+    }
+
+    /// Sends the statistics of a transcoded texture to [`BasisuDiagnosticsPlugin`](crate::BasisuDiagnosticsPlugin), if it's added.
+    fn record_transcoded(
+        &self,
+        stats_sender: Option<&Sender<TranscodeStats>>,
+        input_bytes: usize,
+        output_bytes: usize,
+        time: Option<Instant>,
+    ) {
+        if let Some(sender) = stats_sender
+            && let Some(time) = time
+        {
+            // The receiver only goes away with the app.
+            let _ = sender.send(TranscodeStats {
+                format: self.format,
+                input_bytes,
+                output_bytes,
+                duration: Some(time.elapsed()),
+            });
+        }
+    }
+
+    /// Sends the statistics of a texture loaded from a [`TranscodeCache`] to [`BasisuDiagnosticsPlugin`](crate::BasisuDiagnosticsPlugin), if it's added.
+    fn record_cached(&self, stats_sender: Option<&Sender<TranscodeStats>>, output_bytes: usize) {
+        if let Some(sender) = stats_sender {
+            let _ = sender.send(TranscodeStats {
+                format: self.format,
+                input_bytes: 0,
+                output_bytes,
+                duration: None,
+            });
+        }
     }
 }
 
@@ -225,7 +1399,15 @@ fn texture_transcode_format_to_bevy_format(
     transcoded: TextureTranscodedFormat,
     is_srgb: bool,
 ) -> TextureFormat {
-    let mut fmt = match transcoded {
+    let fmt = try_texture_transcode_format_to_bevy_format(transcoded).unwrap();
+    if is_srgb { fmt.add_srgb_suffix() } else { fmt }
+}
+
+/// Returns `None` for the formats that wgpu doesn't support.
+fn try_texture_transcode_format_to_bevy_format(
+    transcoded: TextureTranscodedFormat,
+) -> Option<TextureFormat> {
+    Some(match transcoded {
         TextureTranscodedFormat::cTFETC1_RGB => TextureFormat::Etc2Rgb8Unorm,
         TextureTranscodedFormat::cTFETC2_RGBA => TextureFormat::Etc2Rgba8Unorm,
         TextureTranscodedFormat::cTFBC1_RGB => TextureFormat::Bc1RgbaUnorm,
@@ -233,17 +1415,10 @@ fn texture_transcode_format_to_bevy_format(
         TextureTranscodedFormat::cTFBC4_R => TextureFormat::Bc4RUnorm,
         TextureTranscodedFormat::cTFBC5_RG => TextureFormat::Bc5RgUnorm,
         TextureTranscodedFormat::cTFBC7_RGBA => TextureFormat::Bc7RgbaUnorm,
-        TextureTranscodedFormat::cTFPVRTC1_4_RGB => unreachable!(),
-        TextureTranscodedFormat::cTFPVRTC1_4_RGBA => unreachable!(),
         TextureTranscodedFormat::cTFASTC_4x4_RGBA => TextureFormat::Astc {
             block: AstcBlock::B4x4,
             channel: AstcChannel::Unorm,
         },
-        TextureTranscodedFormat::cTFATC_RGB => unreachable!(),
-        TextureTranscodedFormat::cTFATC_RGBA => unreachable!(),
-        TextureTranscodedFormat::cTFFXT1_RGB => unreachable!(),
-        TextureTranscodedFormat::cTFPVRTC2_4_RGB => unreachable!(),
-        TextureTranscodedFormat::cTFPVRTC2_4_RGBA => unreachable!(),
         TextureTranscodedFormat::cTFETC2_EAC_R11 => TextureFormat::EacR11Unorm,
         TextureTranscodedFormat::cTFETC2_EAC_RG11 => TextureFormat::EacRg11Unorm,
         TextureTranscodedFormat::cTFBC6H => TextureFormat::Bc6hRgbUfloat,
@@ -252,24 +1427,14 @@ fn texture_transcode_format_to_bevy_format(
             channel: AstcChannel::Hdr,
         },
         TextureTranscodedFormat::cTFRGBA32 => TextureFormat::Rgba8Unorm,
-        TextureTranscodedFormat::cTFRGB565 => unreachable!(),
-        TextureTranscodedFormat::cTFBGR565 => unreachable!(),
-        TextureTranscodedFormat::cTFRGBA4444 => unreachable!(),
-        TextureTranscodedFormat::cTFRGB_HALF => unreachable!(),
         TextureTranscodedFormat::cTFRGBA_HALF => TextureFormat::Rgba16Float,
         TextureTranscodedFormat::cTFRGB_9E5 => TextureFormat::Rgb9e5Ufloat,
         TextureTranscodedFormat::cTFASTC_HDR_6x6_RGBA => TextureFormat::Astc {
             block: AstcBlock::B6x6,
             channel: AstcChannel::Hdr,
         },
-        TextureTranscodedFormat::cTFTotalTextureFormats => unreachable!(),
-        TextureTranscodedFormat::cTFBC7_ALT => unreachable!(),
-        _ => unreachable!(),
-    };
-    if is_srgb {
-        fmt = fmt.add_srgb_suffix();
-    }
-    fmt
+        _ => return None,
+    })
 }
 
 fn texture_bevy_format_to_transcode_format(
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+
+/// The label of the [`Ktx2Metadata`] sub-asset of a KTX2 texture loaded by [`BasisuLoader`](crate::BasisuLoader).
+///
+/// E.g. `asset_server.load::<Ktx2Metadata>("texture.basisu_ktx2#metadata")`.
+pub const KTX2_METADATA_LABEL: &str = "metadata";
+
+/// The key/value data of a Basis Universal KTX2 texture, such as `KTXorientation`, `KTXswizzle`, `KTXwriter`, `KTXanimData` and custom application keys.
+///
+/// Loaded as the [`KTX2_METADATA_LABEL`] labeled sub-asset. Legacy `.basis` files don't have it.
+/// KTX2 files delegated to Bevy's `ImageLoader` have their own key/value data, e.g. that of the original texture for textures pre-transcoded by `BasisuProcessor`.
+#[derive(Asset, TypePath, Debug, Clone, Default)]
+pub struct Ktx2Metadata {
+    /// The key/value pairs in file order.
+    ///
+    /// Keys are stored without their NUL terminator. Values are kept as is, string values usually include a NUL terminator.
+    pub key_values: Vec<(String, Vec<u8>)>,
+}
+
+impl Ktx2Metadata {
+    pub(crate) fn from_raw(key_values: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
+        Self {
+            key_values: key_values
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = key.strip_suffix(&[0]).unwrap_or(&key);
+                    (String::from_utf8_lossy(key).into_owned(), value)
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the value of the first entry with the given key.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.key_values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// Returns the value of the first entry with the given key as a string, without its NUL terminator.
+    ///
+    /// Returns `None` if there is no such entry or the value isn't valid UTF-8.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        let value = self.get(key)?;
+        core::str::from_utf8(value.strip_suffix(&[0]).unwrap_or(value)).ok()
+    }
+}
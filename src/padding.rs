@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The label of the [`BasisuPadding`] sub-asset of a texture padded by [`BasisuLoader`](crate::BasisuLoader).
+///
+/// E.g. `asset_server.load::<BasisuPadding>("texture.basisu_ktx2#padding")`.
+pub const BASISU_PADDING_LABEL: &str = "padding";
+
+/// What to do when the size of a texture isn't a multiple of the block size of the compressed transcode target, e.g. 4x4 for BC7 or 6x6 for ASTC HDR 6x6.
+///
+/// wgpu can't create such textures, see <https://github.com/gfx-rs/wgpu/issues/7677>.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnalignedSizePolicy {
+    /// Transcode to `Rgba8Unorm`, or `Rgba16Float` for HDR textures, which have no size restrictions but use more memory.
+    #[default]
+    Uncompressed,
+    /// Pad the size of the [`Image`] up to the block size. The padding is filled with the edge blocks of the encoded data.
+    ///
+    /// A [`BasisuPadding`] sub-asset labeled [`BASISU_PADDING_LABEL`] is added, UV coordinates have to be scaled by [`BasisuPadding::uv_scale`].
+    /// Mip levels whose block count would change by the padding are dropped with a warning.
+    Pad,
+    /// Fail with [`BasisuLoaderError::UnalignedSize`](crate::BasisuLoaderError::UnalignedSize).
+    Error,
+}
+
+/// The size of a texture that was padded to the block size of its transcode target, see [`UnalignedSizePolicy::Pad`].
+#[derive(Asset, TypePath, Debug, Clone, Copy, PartialEq)]
+pub struct BasisuPadding {
+    /// The size of the texture before padding.
+    pub size: UVec2,
+    /// The size of the padded [`Image`].
+    pub padded_size: UVec2,
+}
+
+impl BasisuPadding {
+    /// The scale to apply to UV coordinates so that they cover the texture without the padding.
+    pub fn uv_scale(&self) -> Vec2 {
+        self.size.as_vec2() / self.padded_size.as_vec2()
+    }
+}
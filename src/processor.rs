@@ -0,0 +1,544 @@
+use bevy::asset::AsyncWriteExt;
+use bevy::asset::io::Writer;
+use bevy::asset::meta::{AssetAction, AssetMeta};
+use bevy::asset::processor::{Process, ProcessContext, ProcessError};
+use bevy::asset::saver::{AssetSaver, SavedAsset};
+use bevy::image::{ImageFormat, ImageFormatSetting, ImageLoader, ImageLoaderSettings};
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    AstcBlock, AstcChannel, TextureDataOrder, TextureDimension, TextureFormat,
+    TextureViewDimension, WgpuFeatures as Features,
+};
+use bevy_basisu_loader_sys::TextureCompressionMethod;
+use core::num::NonZeroUsize;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::loader::{BasisuLoader, BasisuLoaderSettings, ktx2_key_value, ktx2_key_values};
+use crate::padding::UnalignedSizePolicy;
+use crate::target::{TargetPriorityRule, TranscodeTarget};
+
+/// The GPU formats [`BasisuProcessor`] pre-transcodes textures to, usually one profile per platform.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscodeProfile {
+    /// Bc7Rgba for LDR (Bc5Rg/Bc4R for ETC1S with RG/R channels) and Bc6hRgbUfloat for HDR textures, for desktop GPUs.
+    #[default]
+    DesktopBc,
+    /// Astc 4x4 for LDR textures, for mobile GPUs.
+    ///
+    /// HDR textures are kept as Basis Universal, since Bevy's [`ImageLoader`] can't load ASTC HDR from KTX2.
+    MobileAstc,
+    /// Etc2Rgba8/Etc2Rgb8/EacRg11/EacR11 for LDR and Rgba16Float for HDR textures, for mobile GPUs without ASTC.
+    MobileEtc2,
+    /// Rgba8 for LDR and Rgba16Float for HDR textures.
+    Uncompressed,
+}
+
+impl TranscodeProfile {
+    /// The texture compression features of the devices the profile is for.
+    fn features(self) -> Features {
+        match self {
+            Self::DesktopBc => Features::TEXTURE_COMPRESSION_BC,
+            Self::MobileAstc => {
+                Features::TEXTURE_COMPRESSION_ASTC | Features::TEXTURE_COMPRESSION_ASTC_HDR
+            }
+            Self::MobileEtc2 => Features::TEXTURE_COMPRESSION_ETC2,
+            Self::Uncompressed => Features::empty(),
+        }
+    }
+
+    /// Whether `format` is one of the formats of the profile, rather than the uncompressed fallback of an unaligned size or an unsupported Basis Universal format.
+    fn has_format(self, format: TextureFormat) -> bool {
+        match self {
+            Self::Uncompressed => true,
+            Self::MobileEtc2 if format == TextureFormat::Rgba16Float => true,
+            _ => {
+                let required = format.required_features();
+                !required.is_empty() && self.features().contains(required)
+            }
+        }
+    }
+
+    /// Returns a loader that transcodes to the formats of the profile, independent of the device.
+    fn loader(self, max_transcode_tasks: Option<NonZeroUsize>) -> BasisuLoader {
+        let target = match self {
+            Self::DesktopBc => TranscodeTarget::Bc,
+            Self::MobileAstc => TranscodeTarget::Astc,
+            Self::MobileEtc2 => TranscodeTarget::Etc2,
+            Self::Uncompressed => TranscodeTarget::Uncompressed,
+        };
+        let mut loader = BasisuLoader::from_features(self.features());
+        loader.max_transcode_tasks = max_transcode_tasks;
+        loader.target_priority = vec![TargetPriorityRule {
+            basis_format: None,
+            channel_type: crate::ChannelType::Auto,
+            targets: vec![target, TranscodeTarget::Uncompressed],
+        }];
+        loader
+    }
+}
+
+/// Settings for processing a texture using [`BasisuProcessor`].
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct BasisuProcessorSettings {
+    /// The profile to transcode to, or `None` for [`BasisuProcessor::default_profile`].
+    pub profile: Option<TranscodeProfile>,
+    /// The settings to transcode with, and to load the processed texture with.
+    ///
+    /// Textures whose size isn't a multiple of the block size of the profile are kept as they are, so [`UnalignedSizePolicy`] is applied at runtime.
+    pub loader_settings: BasisuLoaderSettings,
+    /// Don't embed the original texture in the pre-transcoded file.
+    ///
+    /// The file is about half the size, but it can only be loaded on devices that support the formats of the profile
+    /// and [`BasisuLoaderSettings::decoded_copy`] isn't available.
+    pub strip_original: bool,
+}
+
+/// The KTX2 key of the original Basis Universal texture embedded by [`BasisuProcessor`].
+pub(crate) const BASISU_ORIGINAL_KEY: &[u8] = b"bevy_basisu_original";
+
+/// A [`Process`] that pre-transcodes Basis Universal textures to the formats of a [`TranscodeProfile`] at asset processing time, so clients don't transcode at runtime.
+///
+/// The processed texture is a KTX2 file without supercompression that Bevy's [`ImageLoader`] can load, see [`Ktx2Saver`].
+/// It keeps the `.basisu_ktx2` or `.basis` extension and is loaded by [`BasisuLoader`] with the `ktx2` feature, which delegates it to the [`ImageLoader`].
+/// The original texture is embedded in its key/value data, and transcoded at runtime instead on devices that don't support the format of the profile,
+/// unless [`BasisuProcessorSettings::strip_original`] is set.
+///
+/// The original texture is kept as it is if it can't be transcoded to the formats of the profile, e.g. because its size isn't a multiple of the block size
+/// or the target is ASTC HDR which the [`ImageLoader`] can't load, and for animated textures unless [`BasisuLoaderSettings::load_animation_as_array`] is set.
+///
+/// Pre-transcoded textures keep the key/value data of the original as the [`Ktx2Metadata`](crate::Ktx2Metadata) sub-asset,
+/// except `KTXorientation` and `KTXswizzle` which are already applied.
+///
+/// [`BasisuLoaderPlugin`](crate::BasisuLoaderPlugin) registers it, but doesn't make it the default processor of any extension.
+/// Select it in the `.meta` file of a texture, or make it the default with `app.set_default_asset_processor::<BasisuProcessor>("basisu_ktx2")`.
+/// Register it again with another [`Self::default_profile`] to change the profile of all textures, e.g. per build target.
+#[derive(Default, Debug, Clone)]
+pub struct BasisuProcessor {
+    /// The profile of textures whose [`BasisuProcessorSettings::profile`] is `None`.
+    pub default_profile: TranscodeProfile,
+    /// The maximum number of tasks used to transcode a single KTX2 texture, see [`BasisuLoaderPlugin::max_transcode_tasks`](crate::BasisuLoaderPlugin::max_transcode_tasks).
+    pub max_transcode_tasks: Option<NonZeroUsize>,
+}
+
+impl Process for BasisuProcessor {
+    type Settings = BasisuProcessorSettings;
+
+    type OutputLoader = BasisuLoader;
+
+    async fn process(
+        &self,
+        context: &mut ProcessContext<'_>,
+        meta: AssetMeta<(), Self>,
+        writer: &mut Writer,
+    ) -> Result<BasisuLoaderSettings, ProcessError> {
+        let AssetAction::Process { settings, .. } = meta.asset else {
+            return Err(ProcessError::WrongMetaType);
+        };
+        let loader_settings = settings.loader_settings;
+        let profile = settings.profile.unwrap_or(self.default_profile);
+        let path = context.path().clone();
+
+        let animated = ktx2_key_value(context.asset_bytes(), b"KTXanimData").is_some();
+        let transcoded = if animated && !loader_settings.load_animation_as_array {
+            None
+        } else {
+            // Unaligned textures get the uncompressed fallback, which isn't a format of the profile, so they are kept as they are.
+            let transcode_settings = BasisuLoaderSettings {
+                unaligned_size: UnalignedSizePolicy::Uncompressed,
+                decoded_copy: false,
+                progressive_preview_size: None,
+                ..loader_settings.clone()
+            };
+            let image = profile
+                .loader(self.max_transcode_tasks)
+                .transcode_image(context.asset_bytes().to_vec(), &transcode_settings, &path)
+                .await
+                .map_err(|err| ProcessError::AssetTransformError(err.into()))?;
+            let original = context.asset_bytes();
+            let mut key_values = ktx2_key_values(original);
+            if !loader_settings.ignore_orientation_and_swizzle {
+                key_values.retain(|(key, _)| !matches!(*key, b"KTXorientation" | b"KTXswizzle"));
+            }
+            if !settings.strip_original {
+                key_values.push((BASISU_ORIGINAL_KEY, original));
+            }
+            let format = image.texture_descriptor.format;
+            if !profile.has_format(format) {
+                None
+            } else {
+                match write_ktx2(&image, &key_values) {
+                    Ok(data) => Some((data, format.is_srgb())),
+                    Err(Ktx2SaverError::UnsupportedFormat(_)) => None,
+                    Err(err) => return Err(ProcessError::AssetSaveError(err.into())),
+                }
+            }
+        };
+
+        let (data, output_settings) = match &transcoded {
+            Some((data, is_srgb)) => (
+                data.as_slice(),
+                BasisuLoaderSettings {
+                    is_srgb: Some(*is_srgb),
+                    ..loader_settings
+                },
+            ),
+            None => {
+                debug!("Keeping the original Basis Universal texture {path}");
+                (context.asset_bytes(), loader_settings)
+            }
+        };
+        writer
+            .write_all(data)
+            .await
+            .map_err(|err| ProcessError::AssetWriterError {
+                path,
+                err: err.into(),
+            })?;
+        Ok(output_settings)
+    }
+}
+
+/// An [`AssetSaver`] that saves an [`Image`] as a KTX2 file without supercompression, that Bevy's [`ImageLoader`] can load.
+///
+/// Supports the formats [`BasisuLoader`] transcodes to, except ASTC HDR, and mip major image data.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Ktx2Saver;
+
+/// An error when saving an image using [`Ktx2Saver`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum Ktx2SaverError {
+    /// An error occurred while writing the KTX2 file.
+    #[error("Failed to write KTX2 file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The format has no `vkFormat` that Bevy's [`ImageLoader`] can load.
+    #[error("Saving {0:?} textures as KTX2 isn't supported")]
+    UnsupportedFormat(TextureFormat),
+    /// The image has no data, or its data is layer major.
+    #[error("The image has no mip major data in the main world")]
+    NoData,
+    /// The data size doesn't match the size, format and mip level count of the image.
+    #[error("Expected {expected} bytes of texture data, got {actual}")]
+    DataSize { expected: usize, actual: usize },
+}
+
+impl AssetSaver for Ktx2Saver {
+    type Asset = Image;
+
+    type Settings = ();
+
+    type OutputLoader = ImageLoader;
+
+    type Error = Ktx2SaverError;
+
+    async fn save(
+        &self,
+        writer: &mut Writer,
+        asset: SavedAsset<'_, Image>,
+        _settings: &(),
+    ) -> Result<ImageLoaderSettings, Ktx2SaverError> {
+        let image = asset.get();
+        writer.write_all(&write_ktx2(image, &[])?).await?;
+        Ok(ImageLoaderSettings {
+            format: ImageFormatSetting::Format(ImageFormat::Ktx2),
+            texture_format: None,
+            is_srgb: image.texture_descriptor.format.is_srgb(),
+            sampler: image.sampler.clone(),
+            asset_usage: image.asset_usage,
+        })
+    }
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const KTX2_HEADER_SIZE: usize = 80;
+const KTX2_DFD_SIZE: usize = 28;
+const KHR_DF_MODEL_RGBSDA: u8 = 1;
+const KHR_DF_MODEL_BC1A: u8 = 128;
+const KHR_DF_MODEL_BC3: u8 = 130;
+const KHR_DF_MODEL_BC4: u8 = 131;
+const KHR_DF_MODEL_BC5: u8 = 132;
+const KHR_DF_MODEL_BC6H: u8 = 133;
+const KHR_DF_MODEL_BC7: u8 = 134;
+const KHR_DF_MODEL_ETC2: u8 = 161;
+const KHR_DF_MODEL_ASTC: u8 = 162;
+const KHR_DF_PRIMARIES_BT709: u8 = 1;
+const KHR_DF_TRANSFER_LINEAR: u8 = 1;
+const KHR_DF_TRANSFER_SRGB: u8 = 2;
+
+/// Returns the `vkFormat` and the data format descriptor color model of a format.
+fn ktx2_format(format: TextureFormat) -> Option<(u32, u8)> {
+    Some(match format {
+        TextureFormat::Rgba8Unorm => (37, KHR_DF_MODEL_RGBSDA),
+        TextureFormat::Rgba8UnormSrgb => (43, KHR_DF_MODEL_RGBSDA),
+        TextureFormat::Rgba16Float => (97, KHR_DF_MODEL_RGBSDA),
+        TextureFormat::Rgb9e5Ufloat => (123, KHR_DF_MODEL_RGBSDA),
+        TextureFormat::Bc1RgbaUnorm => (133, KHR_DF_MODEL_BC1A),
+        TextureFormat::Bc1RgbaUnormSrgb => (134, KHR_DF_MODEL_BC1A),
+        TextureFormat::Bc3RgbaUnorm => (137, KHR_DF_MODEL_BC3),
+        TextureFormat::Bc3RgbaUnormSrgb => (138, KHR_DF_MODEL_BC3),
+        TextureFormat::Bc4RUnorm => (139, KHR_DF_MODEL_BC4),
+        TextureFormat::Bc5RgUnorm => (141, KHR_DF_MODEL_BC5),
+        TextureFormat::Bc6hRgbUfloat => (143, KHR_DF_MODEL_BC6H),
+        TextureFormat::Bc7RgbaUnorm => (145, KHR_DF_MODEL_BC7),
+        TextureFormat::Bc7RgbaUnormSrgb => (146, KHR_DF_MODEL_BC7),
+        TextureFormat::Etc2Rgb8Unorm => (147, KHR_DF_MODEL_ETC2),
+        TextureFormat::Etc2Rgb8UnormSrgb => (148, KHR_DF_MODEL_ETC2),
+        TextureFormat::Etc2Rgba8Unorm => (151, KHR_DF_MODEL_ETC2),
+        TextureFormat::Etc2Rgba8UnormSrgb => (152, KHR_DF_MODEL_ETC2),
+        TextureFormat::EacR11Unorm => (153, KHR_DF_MODEL_ETC2),
+        TextureFormat::EacRg11Unorm => (155, KHR_DF_MODEL_ETC2),
+        TextureFormat::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::Unorm,
+        } => (157, KHR_DF_MODEL_ASTC),
+        TextureFormat::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::UnormSrgb,
+        } => (158, KHR_DF_MODEL_ASTC),
+        _ => return None,
+    })
+}
+
+/// Returns the texture compression a `vkFormat` needs, [`TextureCompressionMethod::NONE`] for uncompressed formats.
+pub(crate) fn ktx2_compression_method(vk_format: u32) -> TextureCompressionMethod {
+    match vk_format {
+        131..=146 => TextureCompressionMethod::BC,
+        147..=156 => TextureCompressionMethod::ETC2,
+        157..=184 => TextureCompressionMethod::ASTC_LDR,
+        // VK_FORMAT_ASTC_*_SFLOAT_BLOCK
+        1000066000..=1000066013 => TextureCompressionMethod::ASTC_HDR,
+        _ => TextureCompressionMethod::NONE,
+    }
+}
+
+/// Writes a KTX2 file without supercompression, with the given key/value data sorted by key.
+///
+/// The data format descriptor only has the header of a basic descriptor block, loaders use the `vkFormat`.
+pub(crate) fn write_ktx2(
+    image: &Image,
+    key_values: &[(&[u8], &[u8])],
+) -> Result<Vec<u8>, Ktx2SaverError> {
+    let descriptor = &image.texture_descriptor;
+    let format = descriptor.format;
+    let (vk_format, color_model) =
+        ktx2_format(format).ok_or(Ktx2SaverError::UnsupportedFormat(format))?;
+    let data = image
+        .data
+        .as_deref()
+        .filter(|_| image.data_order == TextureDataOrder::MipMajor)
+        .ok_or(Ktx2SaverError::NoData)?;
+
+    let layers = descriptor.size.depth_or_array_layers;
+    let view_dimension = image
+        .texture_view_descriptor
+        .as_ref()
+        .and_then(|descriptor| descriptor.dimension);
+    let (layer_count, face_count) = match view_dimension {
+        Some(TextureViewDimension::Cube) => (0, 6),
+        Some(TextureViewDimension::CubeArray) => (layers / 6, 6),
+        Some(TextureViewDimension::D2Array) => (layers, 1),
+        _ => (0, 1),
+    };
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap() as usize;
+    let level_sizes = (0..descriptor.mip_level_count)
+        .map(|level| {
+            let size = descriptor.size.mip_level_size(level, TextureDimension::D2);
+            (size.width.div_ceil(block_width) * size.height.div_ceil(block_height)) as usize
+                * block_size
+                * size.depth_or_array_layers as usize
+        })
+        .collect::<Vec<_>>();
+    let expected = level_sizes.iter().sum();
+    if data.len() != expected {
+        return Err(Ktx2SaverError::DataSize {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    // Each key/value pair is its length, the NUL terminated key and the value, padded to 4 bytes.
+    let mut key_values = key_values.to_vec();
+    key_values.sort_by_key(|(key, _)| *key);
+    let mut kvd = Vec::new();
+    for (key, value) in key_values {
+        kvd.extend_from_slice(&((key.len() + 1 + value.len()) as u32).to_le_bytes());
+        kvd.extend_from_slice(key);
+        kvd.push(0);
+        kvd.extend_from_slice(value);
+        kvd.resize(kvd.len().next_multiple_of(4), 0);
+    }
+
+    // The levels are stored from the smallest to the largest, each aligned to the least common multiple of the block size and 4.
+    let dfd_offset = KTX2_HEADER_SIZE + level_sizes.len() * 24;
+    let kvd_offset = dfd_offset + KTX2_DFD_SIZE;
+    let mut level_offsets = vec![0; level_sizes.len()];
+    let mut end = kvd_offset + kvd.len();
+    for (offset, size) in level_offsets.iter_mut().zip(&level_sizes).rev() {
+        *offset = end.next_multiple_of(block_size.max(4));
+        end = *offset + size;
+    }
+
+    let mut file = Vec::with_capacity(end);
+    file.extend_from_slice(&KTX2_IDENTIFIER);
+    let type_size = match format {
+        TextureFormat::Rgba16Float => 2,
+        TextureFormat::Rgb9e5Ufloat => 4,
+        _ => 1,
+    };
+    for value in [
+        vk_format,
+        type_size,
+        descriptor.size.width,
+        descriptor.size.height,
+        0,
+        layer_count,
+        face_count,
+        descriptor.mip_level_count,
+        0,
+        dfd_offset as u32,
+        KTX2_DFD_SIZE as u32,
+        if kvd.is_empty() { 0 } else { kvd_offset as u32 },
+        kvd.len() as u32,
+    ] {
+        file.extend_from_slice(&value.to_le_bytes());
+    }
+    file.extend_from_slice(&[0; 16]);
+    for (&offset, &size) in level_offsets.iter().zip(&level_sizes) {
+        for value in [offset, size, size] {
+            file.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+    }
+
+    let transfer = if format.is_srgb() {
+        KHR_DF_TRANSFER_SRGB
+    } else {
+        KHR_DF_TRANSFER_LINEAR
+    };
+    for value in [
+        KTX2_DFD_SIZE as u32,
+        0,
+        2 | ((KTX2_DFD_SIZE as u32 - 4) << 16),
+        u32::from_le_bytes([color_model, KHR_DF_PRIMARIES_BT709, transfer, 0]),
+        u32::from_le_bytes([block_width as u8 - 1, block_height as u8 - 1, 0, 0]),
+        block_size as u32,
+        0,
+    ] {
+        file.extend_from_slice(&value.to_le_bytes());
+    }
+    file.extend_from_slice(&kvd);
+
+    let mut level_data = Vec::with_capacity(level_sizes.len());
+    let mut rest = data;
+    for &size in &level_sizes {
+        let (level, next) = rest.split_at(size);
+        level_data.push(level);
+        rest = next;
+    }
+    for (&offset, level) in level_offsets.iter().zip(&level_data).rev() {
+        file.resize(offset, 0);
+        file.extend_from_slice(level);
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::image::{CompressedImageFormats, ktx2_buffer_to_image};
+    use bevy::render::render_resource::{Extent3d, TextureViewDescriptor};
+
+    #[test]
+    fn ktx2_levels() {
+        let mut image = Image::default();
+        image.texture_descriptor.size = Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 2,
+        };
+        image.texture_descriptor.format = TextureFormat::Bc7RgbaUnormSrgb;
+        image.texture_descriptor.mip_level_count = 3;
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        // Level 0 has 4 blocks per layer, levels 1 and 2 have 1 block per layer.
+        image.data = Some((0..6 * 16 * 2).map(|i| i as u8).collect());
+        image.data_order = TextureDataOrder::MipMajor;
+        let file = write_ktx2(&image, &[]).unwrap();
+        assert!(!crate::is_basisu_ktx2(&file));
+
+        let loaded = ktx2_buffer_to_image(&file, CompressedImageFormats::BC, true).unwrap();
+        assert_eq!(
+            loaded.texture_descriptor.format,
+            image.texture_descriptor.format
+        );
+        assert_eq!(
+            loaded.texture_descriptor.size,
+            image.texture_descriptor.size
+        );
+        assert_eq!(loaded.texture_descriptor.mip_level_count, 3);
+        assert_eq!(loaded.data, image.data);
+        assert_eq!(
+            loaded.texture_view_descriptor.unwrap().dimension,
+            Some(TextureViewDimension::D2Array)
+        );
+    }
+
+    #[test]
+    fn ktx2_key_values_and_fallback() {
+        let mut image = Image::default();
+        image.texture_descriptor.size = Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 1,
+        };
+        image.texture_descriptor.format = TextureFormat::Bc7RgbaUnorm;
+        image.data = Some(vec![7; 16]);
+        image.data_order = TextureDataOrder::MipMajor;
+        let file = write_ktx2(
+            &image,
+            &[
+                (BASISU_ORIGINAL_KEY, b"original"),
+                (b"KTXwriter", b"test\0"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            ktx2_key_values(&file),
+            [
+                (&b"KTXwriter"[..], &b"test\0"[..]),
+                (BASISU_ORIGINAL_KEY, &b"original"[..]),
+            ]
+        );
+        let loaded = ktx2_buffer_to_image(&file, CompressedImageFormats::BC, false).unwrap();
+        assert_eq!(loaded.data, image.data);
+
+        // The original is only transcoded on devices without the format of the profile.
+        let vk_format = u32::from_le_bytes(file[12..16].try_into().unwrap());
+        assert_eq!(
+            ktx2_compression_method(vk_format),
+            TextureCompressionMethod::BC
+        );
+        assert_eq!(
+            ktx2_compression_method(ktx2_format(TextureFormat::Rgba16Float).unwrap().0),
+            TextureCompressionMethod::NONE
+        );
+    }
+
+    #[test]
+    fn profile_formats() {
+        assert!(TranscodeProfile::DesktopBc.has_format(TextureFormat::Bc7RgbaUnormSrgb));
+        assert!(TranscodeProfile::DesktopBc.has_format(TextureFormat::Bc6hRgbUfloat));
+        // The uncompressed fallback of unaligned textures keeps the original texture.
+        assert!(!TranscodeProfile::DesktopBc.has_format(TextureFormat::Rgba8UnormSrgb));
+        assert!(!TranscodeProfile::MobileAstc.has_format(TextureFormat::Etc2Rgba8Unorm));
+        assert!(TranscodeProfile::MobileEtc2.has_format(TextureFormat::EacRg11Unorm));
+        assert!(TranscodeProfile::MobileEtc2.has_format(TextureFormat::Rgba16Float));
+        assert!(!TranscodeProfile::MobileEtc2.has_format(TextureFormat::Rgba8Unorm));
+        assert!(TranscodeProfile::Uncompressed.has_format(TextureFormat::Rgba8Unorm));
+    }
+}
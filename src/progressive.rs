@@ -0,0 +1,159 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use bevy::asset::{AssetPath, LoadState};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::loader::BASISU_DECODED_LABEL;
+
+/// A full mip chain transcoded after its preview was loaded, see [`BasisuLoaderSettings::progressive_preview_size`](crate::BasisuLoaderSettings::progressive_preview_size).
+pub(crate) struct ProgressiveImage {
+    pub(crate) path: AssetPath<'static>,
+    /// The load that transcoded the image, see [`ProgressiveSender::start`].
+    pub(crate) generation: u64,
+    pub(crate) image: Image,
+    /// The full [`BasisuLoaderSettings::decoded_copy`](crate::BasisuLoaderSettings::decoded_copy), labeled [`BASISU_DECODED_LABEL`].
+    pub(crate) decoded: Option<Image>,
+}
+
+/// Sends [`ProgressiveImage`]s from the loader tasks to [`replace_progressive_images`].
+///
+/// Each progressive load gets a new generation and only the latest one of a path is kept, so a full mip chain
+/// transcoded by an earlier load never replaces a reloaded image.
+#[derive(Clone)]
+pub(crate) struct ProgressiveSender {
+    sender: Sender<ProgressiveImage>,
+    next_generation: Arc<AtomicU64>,
+    latest: Arc<Mutex<HashMap<AssetPath<'static>, u64>>>,
+}
+
+impl ProgressiveSender {
+    /// Drops the full mip chains of earlier loads of `path`, called whenever it's loaded again.
+    pub(crate) fn cancel(&self, path: &AssetPath<'static>) {
+        self.latest.lock().unwrap().remove(path);
+    }
+
+    /// Starts a progressive load of `path` and returns its generation.
+    pub(crate) fn start(&self, path: &AssetPath<'static>) -> u64 {
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        self.latest.lock().unwrap().insert(path.clone(), generation);
+        generation
+    }
+
+    /// Ends a progressive load of `path` once its full mip chain is added, dropped or failed to transcode.
+    pub(crate) fn finish(&self, path: &AssetPath<'static>, generation: u64) {
+        let mut latest = self.latest.lock().unwrap();
+        if latest.get(path) == Some(&generation) {
+            latest.remove(path);
+        }
+    }
+
+    pub(crate) fn send(&self, image: ProgressiveImage) {
+        // The receiver only goes away with the app.
+        let _ = self.sender.send(image);
+    }
+
+    fn is_latest(&self, path: &AssetPath<'static>, generation: u64) -> bool {
+        self.latest.lock().unwrap().get(path) == Some(&generation)
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct ProgressiveImages {
+    pub(crate) sender: ProgressiveSender,
+    receiver: Mutex<Receiver<ProgressiveImage>>,
+    /// Images whose preview is still loading, i.e. hasn't been added to [`Assets<Image>`] yet.
+    pending: Vec<ProgressiveImage>,
+}
+
+impl Default for ProgressiveImages {
+    fn default() -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        Self {
+            sender: ProgressiveSender {
+                sender,
+                next_generation: Arc::default(),
+                latest: Arc::default(),
+            },
+            receiver: Mutex::new(receiver),
+            pending: Vec::new(),
+        }
+    }
+}
+
+/// Replaces the previews of progressively loaded images with their full mip chain.
+pub(crate) fn replace_progressive_images(
+    mut progressive_images: ResMut<ProgressiveImages>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let progressive_images = &mut *progressive_images;
+    let received = progressive_images.receiver.get_mut().unwrap().try_iter();
+    progressive_images.pending.extend(received);
+    let sender = &progressive_images.sender;
+    for progressive in core::mem::take(&mut progressive_images.pending) {
+        if !sender.is_latest(&progressive.path, progressive.generation) {
+            continue;
+        }
+        let decoded_path = progressive.path.clone().with_label(BASISU_DECODED_LABEL);
+        let handles = [
+            asset_server.get_handle::<Image>(&progressive.path),
+            progressive
+                .decoded
+                .as_ref()
+                .and_then(|_| asset_server.get_handle::<Image>(&decoded_path)),
+        ];
+        let states = handles.each_ref().map(|handle| {
+            handle
+                .as_ref()
+                .and_then(|handle| asset_server.get_load_state(handle.id()))
+        });
+        if states
+            .iter()
+            .any(|state| matches!(state, Some(LoadState::Loading)))
+        {
+            progressive_images.pending.push(progressive);
+            continue;
+        }
+        // The images are dropped if their handles were dropped, they were removed or their load failed in the meantime.
+        let [handle, decoded_handle] = handles;
+        let [state, decoded_state] = states;
+        for (handle, state, image) in [
+            (handle, state, Some(progressive.image)),
+            (decoded_handle, decoded_state, progressive.decoded),
+        ] {
+            if let (Some(handle), Some(LoadState::Loaded), Some(image)) = (handle, state, image)
+                && images.contains(&handle)
+            {
+                let _ = images.insert(&handle, image);
+            }
+        }
+        sender.finish(&progressive.path, progressive.generation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_latest_load_is_kept() {
+        let sender = ProgressiveImages::default().sender;
+        let path = AssetPath::from("texture.basisu_ktx2");
+        let first = sender.start(&path);
+        let second = sender.start(&path);
+        assert!(!sender.is_latest(&path, first));
+        assert!(sender.is_latest(&path, second));
+
+        // Finishing an earlier load keeps the latest one.
+        sender.finish(&path, first);
+        assert!(sender.is_latest(&path, second));
+
+        let third = sender.start(&path);
+        sender.cancel(&path);
+        assert!(!sender.is_latest(&path, third));
+        assert!(sender.latest.lock().unwrap().is_empty());
+    }
+}
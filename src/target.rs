@@ -0,0 +1,94 @@
+use bevy_basisu_loader_sys::TargetPriority;
+use serde::{Deserialize, Serialize};
+
+use crate::loader::{BasisFormat, ChannelType};
+
+/// A group of transcode targets in a [`TargetPriorityRule`]. The format within the group depends on the basis format and channel type.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscodeTarget {
+    /// Astc 4x4 for LDR, Astc HDR 4x4 or 6x6 for HDR textures.
+    Astc,
+    /// Bc7Rgba for LDR (Bc5Rg/Bc4R for ETC1S with RG/R channels), Bc6hRgbUfloat for HDR textures.
+    Bc,
+    /// Etc2Rgba8/Etc2Rgb8/EacRg11/EacR11, LDR textures only.
+    Etc2,
+    /// Rgba8 for LDR, Rgba16Float for HDR textures.
+    Uncompressed,
+}
+
+impl TranscodeTarget {
+    fn to_sys(self) -> bevy_basisu_loader_sys::TranscodeTarget {
+        match self {
+            Self::Astc => bevy_basisu_loader_sys::TranscodeTarget::Astc,
+            Self::Bc => bevy_basisu_loader_sys::TranscodeTarget::Bc,
+            Self::Etc2 => bevy_basisu_loader_sys::TranscodeTarget::Etc2,
+            Self::Uncompressed => bevy_basisu_loader_sys::TranscodeTarget::Uncompressed,
+        }
+    }
+}
+
+/// The transcode targets to try first for textures of a basis format and channel type.
+///
+/// Targets that the device or Basis Universal doesn't support are skipped. If none is left, the default priority listed in [`BasisuLoaderPlugin`](crate::BasisuLoaderPlugin) is used.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TargetPriorityRule {
+    /// The basis format the rule applies to, or `None` for all formats.
+    pub basis_format: Option<BasisFormat>,
+    /// The channel type the rule applies to, or [`ChannelType::Auto`] for all channel types.
+    ///
+    /// This is the channel type of the texture, or [`BasisuLoaderSettings::channel_type_hint`](crate::BasisuLoaderSettings::channel_type_hint) if set.
+    pub channel_type: ChannelType,
+    /// The targets in order of priority, at most 4 are used.
+    pub targets: Vec<TranscodeTarget>,
+}
+
+impl TargetPriorityRule {
+    fn matches(&self, basis_format: BasisFormat, channel_type: ChannelType) -> bool {
+        self.basis_format
+            .is_none_or(|format| format == basis_format)
+            && (self.channel_type == ChannelType::Auto || self.channel_type == channel_type)
+    }
+}
+
+const BASIS_FORMATS: [BasisFormat; 5] = [
+    BasisFormat::Etc1s,
+    BasisFormat::UastcLdr4x4,
+    BasisFormat::UastcHdr4x4,
+    BasisFormat::AstcHdr6x6,
+    BasisFormat::UastcHdr6x6Intermediate,
+];
+
+const CHANNEL_TYPES: [ChannelType; 4] = [
+    ChannelType::Rgba,
+    ChannelType::Rgb,
+    ChannelType::Rg,
+    ChannelType::R,
+];
+
+/// Builds the priority table of the transcoder, the first matching rule applies to each basis format and channel type.
+pub(crate) fn resolve_target_priority<'a>(
+    rules: impl Iterator<Item = &'a TargetPriorityRule> + Clone,
+) -> TargetPriority {
+    let mut priority = TargetPriority::default();
+    for basis_format in BASIS_FORMATS {
+        for channel_type in CHANNEL_TYPES {
+            let Some(rule) = rules
+                .clone()
+                .find(|rule| rule.matches(basis_format, channel_type))
+            else {
+                continue;
+            };
+            let targets = rule
+                .targets
+                .iter()
+                .map(|target| target.to_sys())
+                .collect::<Vec<_>>();
+            priority.set(
+                basis_format.to_sys(),
+                bevy_basisu_loader_sys::ChannelType(channel_type as u8),
+                &targets,
+            );
+        }
+    }
+    priority
+}
@@ -0,0 +1,349 @@
+use bevy::asset::AssetPath;
+use bevy::render::render_resource::{Extent3d, TextureFormat};
+
+use crate::metadata::Ktx2Metadata;
+
+/// A transformation of the transcoded data that honors the `KTXorientation` and `KTXswizzle` key/values of a KTX2 texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Ktx2Transform {
+    flip_x: bool,
+    flip_y: bool,
+    /// The source channel of each output channel, one of `rgba01`.
+    swizzle: Option<[u8; 4]>,
+}
+
+impl Ktx2Transform {
+    /// Reads the transform from the metadata and drops the parts that can't be applied to the transcoded format, with a warning.
+    pub(crate) fn new(
+        metadata: &Ktx2Metadata,
+        format: TextureFormat,
+        extent: Extent3d,
+        levels: u32,
+        path: &AssetPath,
+    ) -> Self {
+        let mut transform = Self::default();
+        if let Some(orientation) = metadata.get_str("KTXorientation") {
+            let orientation = orientation.as_bytes();
+            transform.flip_x = orientation.first() == Some(&b'l');
+            transform.flip_y = orientation.get(1) == Some(&b'u');
+        }
+        if let Some(swizzle) = metadata.get_str("KTXswizzle") {
+            match <[u8; 4]>::try_from(swizzle.as_bytes()) {
+                Ok(swizzle) if swizzle.iter().all(|c| b"rgba01".contains(c)) => {
+                    transform.swizzle = (&swizzle != b"rgba").then_some(swizzle);
+                }
+                _ => bevy::log::warn!("Ignoring invalid KTXswizzle {swizzle:?} of {path}"),
+            }
+        }
+
+        if transform.flip_x && !is_uncompressed(format) {
+            bevy::log::warn!(
+                "Can't honor the horizontal KTXorientation of {path} when transcoding to {format:?}"
+            );
+            transform.flip_x = false;
+        }
+        if transform.flip_y
+            && !is_uncompressed(format)
+            && !(is_bc_flippable(format) && block_rows_flippable(extent.height, levels))
+        {
+            bevy::log::warn!(
+                "Can't honor the vertical KTXorientation of {path} when transcoding to {format:?}"
+            );
+            transform.flip_y = false;
+        }
+        if transform
+            .swizzle
+            .is_some_and(|swizzle| swizzle_is_implied(swizzle, format))
+        {
+            transform.swizzle = None;
+        }
+        if let Some(swizzle) = transform.swizzle
+            && !matches!(
+                format,
+                TextureFormat::Rgba8Unorm
+                    | TextureFormat::Rgba8UnormSrgb
+                    | TextureFormat::Rgba16Float
+            )
+        {
+            bevy::log::warn!(
+                "Can't honor KTXswizzle {:?} of {path} when transcoding to {format:?}",
+                core::str::from_utf8(&swizzle).unwrap()
+            );
+            transform.swizzle = None;
+        }
+        transform
+    }
+
+    pub(crate) fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Applies the transform to MipMajor ordered data.
+    pub(crate) fn apply(
+        &self,
+        data: &mut [u8],
+        format: TextureFormat,
+        extent: Extent3d,
+        levels: u32,
+    ) {
+        let (block_width, block_height) = format.block_dimensions();
+        let block_size = format.block_copy_size(None).unwrap() as usize;
+        let mut offset = 0;
+        for level in 0..levels {
+            let width = (extent.width >> level).max(1);
+            let height = (extent.height >> level).max(1);
+            let row_size = width.div_ceil(block_width) as usize * block_size;
+            let block_rows = height.div_ceil(block_height) as usize;
+            for _ in 0..extent.depth_or_array_layers {
+                let slice = &mut data[offset..offset + row_size * block_rows];
+                offset += slice.len();
+
+                if self.flip_y {
+                    if is_uncompressed(format) {
+                        flip_rows(slice, row_size);
+                    } else {
+                        flip_bc_blocks(slice, row_size, format, height);
+                    }
+                }
+                if self.flip_x {
+                    for row in slice.chunks_exact_mut(row_size) {
+                        flip_row(row, block_size);
+                    }
+                }
+                if let Some(swizzle) = self.swizzle {
+                    swizzle_pixels(slice, format, swizzle);
+                }
+            }
+        }
+    }
+}
+
+fn is_uncompressed(format: TextureFormat) -> bool {
+    format.block_dimensions() == (1, 1)
+}
+
+fn is_bc_flippable(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::Bc1RgbaUnorm
+            | TextureFormat::Bc1RgbaUnormSrgb
+            | TextureFormat::Bc3RgbaUnorm
+            | TextureFormat::Bc3RgbaUnormSrgb
+            | TextureFormat::Bc4RUnorm
+            | TextureFormat::Bc5RgUnorm
+    )
+}
+
+/// Blocks of different rows can't be merged, so every level must fit in a single block row or consist of whole block rows.
+fn block_rows_flippable(height: u32, levels: u32) -> bool {
+    (0..levels).all(|level| {
+        let height = (height >> level).max(1);
+        height < 4 || height.is_multiple_of(4)
+    })
+}
+
+/// Whether sampling the format already produces the swizzled channels, e.g. `rg01` for a two-channel format.
+fn swizzle_is_implied(swizzle: [u8; 4], format: TextureFormat) -> bool {
+    let channels = match format {
+        TextureFormat::Bc4RUnorm | TextureFormat::EacR11Unorm => 1,
+        TextureFormat::Bc5RgUnorm | TextureFormat::EacRg11Unorm => 2,
+        TextureFormat::Etc2Rgb8Unorm
+        | TextureFormat::Etc2Rgb8UnormSrgb
+        | TextureFormat::Bc6hRgbUfloat
+        | TextureFormat::Rgb9e5Ufloat => 3,
+        _ => 4,
+    };
+    swizzle
+        .iter()
+        .enumerate()
+        .all(|(i, &c)| c == b"rgba"[i] || (i >= channels && c == if i == 3 { b'1' } else { b'0' }))
+}
+
+fn flip_rows(slice: &mut [u8], row_size: usize) {
+    let rows = slice.len() / row_size;
+    for row in 0..rows / 2 {
+        let (top, bottom) = slice.split_at_mut((rows - 1 - row) * row_size);
+        top[row * row_size..(row + 1) * row_size].swap_with_slice(&mut bottom[..row_size]);
+    }
+}
+
+fn flip_row(row: &mut [u8], pixel_size: usize) {
+    let pixels = row.len() / pixel_size;
+    for pixel in 0..pixels / 2 {
+        let (left, right) = row.split_at_mut((pixels - 1 - pixel) * pixel_size);
+        left[pixel * pixel_size..(pixel + 1) * pixel_size]
+            .swap_with_slice(&mut right[..pixel_size]);
+    }
+}
+
+/// Flips BC1/BC3/BC4/BC5 data vertically by reversing the block rows and the pixel rows inside each block.
+fn flip_bc_blocks(slice: &mut [u8], row_size: usize, format: TextureFormat, height: u32) {
+    flip_rows(slice, row_size);
+    // Only the first `height` rows of a single block row are valid.
+    let rows = height.min(4) as usize;
+    let block_size = format.block_copy_size(None).unwrap() as usize;
+    for block in slice.chunks_exact_mut(block_size) {
+        match format {
+            TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => {
+                flip_bc1_block(block, rows);
+            }
+            TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => {
+                flip_bc4_block(&mut block[..8], rows);
+                flip_bc1_block(&mut block[8..], rows);
+            }
+            TextureFormat::Bc4RUnorm => flip_bc4_block(block, rows),
+            TextureFormat::Bc5RgUnorm => {
+                flip_bc4_block(&mut block[..8], rows);
+                flip_bc4_block(&mut block[8..], rows);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The 2-bit indices of a row are stored in one byte after the two endpoints.
+fn flip_bc1_block(block: &mut [u8], rows: usize) {
+    block[4..4 + rows].reverse();
+}
+
+/// The 3-bit indices of a row are stored in 12 bits after the two endpoints.
+fn flip_bc4_block(block: &mut [u8], rows: usize) {
+    let mut bytes = [0; 8];
+    bytes[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bytes);
+    let mut flipped = indices;
+    for row in 0..rows {
+        let bits = (indices >> (row * 12)) & 0xfff;
+        let target = (rows - 1 - row) * 12;
+        flipped = (flipped & !(0xfff << target)) | (bits << target);
+    }
+    block[2..8].copy_from_slice(&flipped.to_le_bytes()[..6]);
+}
+
+fn swizzle_pixels(slice: &mut [u8], format: TextureFormat, swizzle: [u8; 4]) {
+    // The bytes of 0 and 1 in a channel.
+    let (channel_size, one): (usize, &[u8]) = match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => (1, &[0xff]),
+        // 1.0 in half precision.
+        TextureFormat::Rgba16Float => (2, &[0x00, 0x3c]),
+        _ => unreachable!(),
+    };
+    for pixel in slice.chunks_exact_mut(channel_size * 4) {
+        let mut source = [0; 8];
+        source[..pixel.len()].copy_from_slice(pixel);
+        for (channel, &c) in pixel.chunks_exact_mut(channel_size).zip(&swizzle) {
+            match c {
+                b'0' => channel.fill(0),
+                b'1' => channel.copy_from_slice(one),
+                c => {
+                    let i = b"rgba".iter().position(|&s| s == c).unwrap() * channel_size;
+                    channel.copy_from_slice(&source[i..i + channel_size]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLIP_Y: Ktx2Transform = Ktx2Transform {
+        flip_x: false,
+        flip_y: true,
+        swizzle: None,
+    };
+
+    fn extent(width: u32, height: u32) -> Extent3d {
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        }
+    }
+
+    #[test]
+    fn flip_uncompressed_mip_chain() {
+        // 2x4, 1x2 and 1x1 levels.
+        let mut data: Vec<u8> = (0..11).collect();
+        FLIP_Y.apply(&mut data, TextureFormat::R8Unorm, extent(2, 4), 3);
+        assert_eq!(data, [6, 7, 4, 5, 2, 3, 0, 1, 9, 8, 10]);
+
+        let mut rows = [0, 1, 2, 3, 4, 5];
+        flip_rows(&mut rows, 2);
+        assert_eq!(rows, [4, 5, 2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn flip_bc1_blocks() {
+        // Two block rows, the block rows and the index rows inside each block are reversed.
+        let mut data = [
+            1, 2, 3, 4, 0x00, 0x11, 0x22, 0x33, 5, 6, 7, 8, 0x44, 0x55, 0x66, 0x77,
+        ];
+        FLIP_Y.apply(&mut data, TextureFormat::Bc1RgbaUnorm, extent(4, 8), 1);
+        assert_eq!(
+            data,
+            [
+                5, 6, 7, 8, 0x77, 0x66, 0x55, 0x44, 1, 2, 3, 4, 0x33, 0x22, 0x11, 0x00
+            ]
+        );
+
+        // Only the valid rows of a block shorter than 4 pixels are flipped.
+        let mut data = [1, 2, 3, 4, 0x00, 0x11, 0x22, 0x33];
+        flip_bc_blocks(&mut data, 8, TextureFormat::Bc1RgbaUnorm, 2);
+        assert_eq!(data, [1, 2, 3, 4, 0x11, 0x00, 0x22, 0x33]);
+    }
+
+    #[test]
+    fn flip_bc4_blocks() {
+        let indices = |block: &[u8]| {
+            let mut bytes = [0; 8];
+            bytes[..6].copy_from_slice(&block[2..8]);
+            u64::from_le_bytes(bytes)
+        };
+        let mut data = [0; 8];
+        data[..2].copy_from_slice(&[0xaa, 0xbb]);
+        data[2..].copy_from_slice(&0xabc_789_456_123_u64.to_le_bytes()[..6]);
+        FLIP_Y.apply(&mut data, TextureFormat::Bc4RUnorm, extent(4, 4), 1);
+        assert_eq!(data[..2], [0xaa, 0xbb]);
+        assert_eq!(indices(&data), 0x123_456_789_abc);
+
+        data[2..].copy_from_slice(&0xabc_789_456_123_u64.to_le_bytes()[..6]);
+        flip_bc_blocks(&mut data, 8, TextureFormat::Bc4RUnorm, 3);
+        assert_eq!(indices(&data), 0xabc_123_456_789);
+    }
+
+    #[test]
+    fn block_rows() {
+        assert!(block_rows_flippable(8, 4));
+        // Levels under 4 pixels fit a single block row.
+        assert!(block_rows_flippable(2, 2));
+        assert!(block_rows_flippable(3, 1));
+        // Blocks would have to be split between block rows.
+        assert!(!block_rows_flippable(6, 1));
+        assert!(!block_rows_flippable(24, 3));
+
+        let metadata = Ktx2Metadata::from_raw(vec![(b"KTXorientation".to_vec(), b"ru\0".to_vec())]);
+        let path = AssetPath::from("texture.basisu_ktx2");
+        let transform =
+            |format, height| Ktx2Transform::new(&metadata, format, extent(4, height), 1, &path);
+        assert_eq!(transform(TextureFormat::Bc1RgbaUnorm, 8), FLIP_Y);
+        assert!(transform(TextureFormat::Bc1RgbaUnorm, 6).is_identity());
+        assert!(transform(TextureFormat::Bc7RgbaUnorm, 8).is_identity());
+        assert_eq!(transform(TextureFormat::Rgba8Unorm, 6), FLIP_Y);
+    }
+
+    #[test]
+    fn swizzle_rg01() {
+        assert!(swizzle_is_implied(*b"rg01", TextureFormat::Bc5RgUnorm));
+        assert!(!swizzle_is_implied(*b"rg01", TextureFormat::Rgba8Unorm));
+
+        let transform = Ktx2Transform {
+            swizzle: Some(*b"rg01"),
+            ..Default::default()
+        };
+        let mut data = [1, 2, 3, 4, 5, 6, 7, 8];
+        transform.apply(&mut data, TextureFormat::Rgba8Unorm, extent(2, 1), 1);
+        assert_eq!(data, [1, 2, 0, 0xff, 5, 6, 0, 0xff]);
+    }
+}